@@ -1,44 +1,32 @@
 use bevy::audio::*;
 use bevy::prelude::*;
 
-use crate::settings::Settings;
+use crate::{
+    collisions::{DamageSource, PlayerHurtEvent},
+    loading::SoundAssets,
+    settings::Settings,
+    AppState,
+};
 
 pub struct GameAudioPlugin;
 
-#[derive(Resource)]
-pub struct GameSounds {
-    pub shoot: Handle<AudioSource>,
-    pub explosion: Handle<AudioSource>,
-}
-
 impl Plugin for GameAudioPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, setup_audio)
-            .add_systems(Update, update_volume);
+            .add_systems(Update, (update_volume, play_damage_sound));
     }
 }
 
-fn setup_audio(mut commands: Commands, asset_server: Res<AssetServer>, settings: Res<Settings>) {
-    // Load and play background music
-    let music = asset_server.load("../assets/8bit-spaceshooter.ogg");
-
+fn setup_audio(mut commands: Commands, sounds: Res<SoundAssets>, settings: Res<Settings>) {
+    // Play background music
     commands.spawn((
-        AudioPlayer::new(music.clone()),
+        AudioPlayer::new(sounds.music.clone()),
         PlaybackSettings {
             mode: PlaybackMode::Loop,
             volume: Volume::new(settings.music_volume),
             ..default()
         },
     ));
-
-    // Load sound effects
-    let shoot_sound = asset_server.load("../assets/laser.ogg");
-    let explosion_sound = asset_server.load("../assets/explosion.ogg");
-
-    commands.insert_resource(GameSounds {
-        shoot: shoot_sound,
-        explosion: explosion_sound,
-    });
 }
 
 fn update_volume(settings: Res<Settings>, mut audio_query: Query<&mut AudioSink>) {
@@ -46,3 +34,28 @@ fn update_volume(settings: Res<Settings>, mut audio_query: Query<&mut AudioSink>
         sink.set_volume(settings.music_volume);
     }
 }
+
+/// A higher pitched explosion when the player is hurt, louder for bigger hits
+fn play_damage_sound(
+    mut commands: Commands,
+    sounds: Res<SoundAssets>,
+    settings: Res<Settings>,
+    mut hurt_events: EventReader<PlayerHurtEvent>,
+) {
+    for event in hurt_events.read() {
+        let loudness = (event.amount as f32 / 5.0).clamp(0.4, 1.0);
+        commands.spawn((
+            StateScoped(AppState::Game),
+            AudioPlayer::new(sounds.explosion.clone()),
+            PlaybackSettings {
+                mode: PlaybackMode::Despawn,
+                volume: Volume::new(settings.effect_volume * loudness),
+                speed: match event.source {
+                    DamageSource::Enemy(_) => 1.3,
+                    DamageSource::Projectile => 1.8,
+                },
+                ..default()
+            },
+        ));
+    }
+}
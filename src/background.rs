@@ -10,7 +10,10 @@ use bevy::{
     sprite::{Material2d, Material2dKey, Material2dPlugin},
 };
 
-const FRAGMENT_SHADER_PATH: &str = "../assets/background_shader.frag";
+use crate::{
+    loading::BACKGROUND_SHADER_PATH,
+    play_area::{PlayArea, PlayAreaCamera},
+};
 
 pub struct BackgroundPlugin;
 
@@ -18,43 +21,63 @@ impl Plugin for BackgroundPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(Material2dPlugin::<BackgroundMaterial>::default())
             .add_systems(Startup, spawn_background)
-            .add_systems(Update, update_shader);
+            .add_systems(
+                Update,
+                (
+                    update_shader,
+                    fit_background_to_play_area.run_if(resource_changed::<PlayArea>),
+                ),
+            );
     }
 }
 
+#[derive(Component)]
+struct Background;
+
 /// Spawn a stretched rectangle material to be the base of the background shader
 fn spawn_background(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<BackgroundMaterial>>,
-    window: Single<&Window>,
+    play_area: Res<PlayArea>,
 ) {
-    let resolution = Vec2::new(
-        window.physical_width() as f32,
-        window.physical_height() as f32,
-    );
     commands.spawn((
+        Background,
         Mesh2d(meshes.add(Rectangle::default())),
         MeshMaterial2d(materials.add(BackgroundMaterial {
-            resolution,
+            resolution: play_area.size,
             time: 0.0,
             speed: 1.0,
         })),
-        Transform::from_translation(Vec3::new(0.0, 0.0, -1.0)).with_scale(resolution.extend(0.0)),
+        Transform::from_translation(Vec3::new(0.0, 0.0, -1.0))
+            .with_scale(play_area.size.extend(0.0)),
     ));
 }
 
+/// Stretch the background over the play area whenever it changes size
+fn fit_background_to_play_area(
+    play_area: Res<PlayArea>,
+    mut query: Query<&mut Transform, With<Background>>,
+) {
+    for mut transform in &mut query {
+        transform.scale = play_area.size.extend(0.0);
+    }
+}
+
 fn update_shader(
     time: Res<Time>,
     mut materials: ResMut<Assets<BackgroundMaterial>>,
-    window: Single<&Window>,
+    camera: Single<&Camera, With<PlayAreaCamera>>,
 ) {
+    // The shader works in screen pixels, so follow the size the play area is
+    // drawn at rather than its size in world units
+    let Some(resolution) = camera.physical_viewport_size() else {
+        return;
+    };
+
     for material in materials.iter_mut() {
         material.1.time = time.elapsed_secs();
-        material.1.resolution = Vec2::new(
-            window.physical_width() as f32,
-            window.physical_height() as f32,
-        );
+        material.1.resolution = resolution.as_vec2();
     }
 }
 
@@ -75,7 +98,7 @@ struct BackgroundMaterial {
 /// You only need to implement functions for features that need non-default behavior. See the Material2d api docs for details!
 impl Material2d for BackgroundMaterial {
     fn fragment_shader() -> ShaderRef {
-        FRAGMENT_SHADER_PATH.into()
+        BACKGROUND_SHADER_PATH.into()
     }
 
     // Bevy assumes by default that vertex shaders use the "vertex" entry point
@@ -0,0 +1,282 @@
+use std::fmt;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    prelude::*,
+};
+use serde::Deserialize;
+
+use crate::{
+    buffs::{BuffDef, BuffKind},
+    components::{MovementSpeed, Shoot},
+    loading::AssetManifest,
+    player::Player,
+    AppState,
+};
+
+/// Relative to the `assets` folder
+const BALANCE_PATH: &str = "gameplay.balance.ron";
+const BALANCE_EXTENSION: &str = "balance.ron";
+
+/// Loads the gameplay tuning values from `assets/gameplay.balance.ron`. With
+/// the `file_watcher` feature, edits to the file apply while the game runs.
+pub struct BalancePlugin;
+impl Plugin for BalancePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Balance>()
+            .register_asset_loader(BalanceLoader)
+            .init_resource::<BalanceFile>()
+            .add_systems(OnEnter(AppState::Loading), add_balance_to_manifest)
+            .add_systems(OnExit(AppState::Loading), insert_balance)
+            .add_systems(
+                PreUpdate,
+                (
+                    update_balance.run_if(resource_exists::<Balance>),
+                    apply_balance_to_player.run_if(resource_exists_and_changed::<Balance>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Gameplay tuning values. Systems read the [`Balance`] resource, which is
+/// kept up to date with the file.
+#[derive(Asset, Resource, TypePath, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Balance {
+    pub player_speed: f32,
+    /// Seconds between shots, before fire rate powerups
+    pub player_shoot_cooldown: f32,
+    pub bullet_speed: f32,
+    /// Seconds the player can't be hurt again after taking damage
+    pub player_invincibility: f32,
+    /// Speed the player is pushed away from what hurt it at, slowing down
+    /// right after
+    pub player_knockback: f32,
+    /// Damage the player does to an enemy by running into it, times the
+    /// damage of the hit zone it runs into
+    pub ramming_damage: i32,
+    pub max_enemies: usize,
+    /// A tick spawns an enemy when a roll from 0 up to
+    /// `enemy_spawn_denominator` comes up at or below this
+    pub enemy_spawn_chance: u32,
+    /// Higher means less enemies
+    pub enemy_spawn_denominator: u32,
+    pub max_powerups: usize,
+    /// The buff a fire rate powerup gives the player
+    pub fire_rate_buff: BuffDef,
+    /// The buff a speed powerup gives the player
+    pub speed_buff: BuffDef,
+    /// Added to the score for picking up a buff that is as strong as it gets
+    pub maxed_buff_score: usize,
+    /// Health given back by a health powerup, up to the player's max health
+    pub health_powerup: i32,
+    /// Chance from `0.0` to `1.0` that a destroyed enemy drops a health
+    /// powerup while the player is at half health or less, before its own
+    /// drops are tried
+    pub low_health_drop_chance: f64,
+}
+
+impl Balance {
+    /// The buff a powerup of `kind` gives
+    pub fn buff(&self, kind: BuffKind) -> &BuffDef {
+        match kind {
+            BuffKind::FireRate => &self.fire_rate_buff,
+            BuffKind::Speed => &self.speed_buff,
+        }
+    }
+
+    /// Every value by name, to report what changed
+    fn values(&self) -> [(&'static str, String); 15] {
+        [
+            ("player_speed", self.player_speed.to_string()),
+            (
+                "player_shoot_cooldown",
+                self.player_shoot_cooldown.to_string(),
+            ),
+            ("bullet_speed", self.bullet_speed.to_string()),
+            (
+                "player_invincibility",
+                self.player_invincibility.to_string(),
+            ),
+            ("player_knockback", self.player_knockback.to_string()),
+            ("ramming_damage", self.ramming_damage.to_string()),
+            ("max_enemies", self.max_enemies.to_string()),
+            ("enemy_spawn_chance", self.enemy_spawn_chance.to_string()),
+            (
+                "enemy_spawn_denominator",
+                self.enemy_spawn_denominator.to_string(),
+            ),
+            ("max_powerups", self.max_powerups.to_string()),
+            ("fire_rate_buff", format!("{:?}", self.fire_rate_buff)),
+            ("speed_buff", format!("{:?}", self.speed_buff)),
+            ("maxed_buff_score", self.maxed_buff_score.to_string()),
+            ("health_powerup", self.health_powerup.to_string()),
+            (
+                "low_health_drop_chance",
+                self.low_health_drop_chance.to_string(),
+            ),
+        ]
+    }
+
+    fn validate(&self) -> Result<(), BalanceError> {
+        let positive = [
+            ("player_speed", self.player_speed),
+            ("player_shoot_cooldown", self.player_shoot_cooldown),
+            ("bullet_speed", self.bullet_speed),
+            ("player_invincibility", self.player_invincibility),
+            ("player_knockback", self.player_knockback),
+        ];
+        if let Some((name, value)) = positive
+            .into_iter()
+            .find(|(_, value)| !value.is_finite() || *value <= 0.0)
+        {
+            return Err(BalanceError::Invalid(format!(
+                "{name} must be above zero, not {value}"
+            )));
+        }
+        for (name, buff) in [
+            ("fire_rate_buff", &self.fire_rate_buff),
+            ("speed_buff", &self.speed_buff),
+        ] {
+            buff.validate()
+                .map_err(|message| BalanceError::Invalid(format!("{name}.{message}")))?;
+        }
+        if self.ramming_damage < 0 {
+            return Err(BalanceError::Invalid(format!(
+                "ramming_damage must not be negative, not {}",
+                self.ramming_damage
+            )));
+        }
+        if self.health_powerup <= 0 {
+            return Err(BalanceError::Invalid(format!(
+                "health_powerup must be above zero, not {}",
+                self.health_powerup
+            )));
+        }
+        if !(0.0..=1.0).contains(&self.low_health_drop_chance) {
+            return Err(BalanceError::Invalid(format!(
+                "low_health_drop_chance must be from 0.0 to 1.0, not {}",
+                self.low_health_drop_chance
+            )));
+        }
+        if self.enemy_spawn_denominator == 0 {
+            return Err(BalanceError::Invalid(
+                "enemy_spawn_denominator must not be zero".into(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Resource)]
+struct BalanceFile(Handle<Balance>);
+
+impl FromWorld for BalanceFile {
+    fn from_world(world: &mut World) -> Self {
+        Self(world.resource::<AssetServer>().load(BALANCE_PATH))
+    }
+}
+
+fn add_balance_to_manifest(mut manifest: ResMut<AssetManifest>, file: Res<BalanceFile>) {
+    manifest.add(file.0.clone());
+}
+
+fn insert_balance(mut commands: Commands, file: Res<BalanceFile>, assets: Res<Assets<Balance>>) {
+    if let Some(balance) = assets.get(&file.0) {
+        commands.insert_resource(balance.clone());
+    }
+}
+
+/// Pick up edits to the file, logging each value that changed
+fn update_balance(
+    mut events: EventReader<AssetEvent<Balance>>,
+    file: Res<BalanceFile>,
+    assets: Res<Assets<Balance>>,
+    mut balance: ResMut<Balance>,
+) {
+    for event in events.read() {
+        if !event.is_modified(&file.0) {
+            continue;
+        }
+        let Some(new) = assets.get(&file.0) else {
+            continue;
+        };
+
+        for ((name, old), (_, new)) in balance.values().into_iter().zip(new.values()) {
+            if old != new {
+                info!("Balance changed {name} from {old} to {new}");
+            }
+        }
+        *balance = new.clone();
+    }
+}
+
+/// The player keeps its speed and cooldown in components, so update those
+/// along with the balance
+fn apply_balance_to_player(
+    balance: Res<Balance>,
+    mut query: Query<(&mut MovementSpeed, &mut Shoot), With<Player>>,
+) {
+    for (mut speed, mut shoot) in query.iter_mut() {
+        speed.0 = balance.player_speed;
+        shoot.seconds = balance.player_shoot_cooldown;
+    }
+}
+
+#[derive(Debug)]
+pub enum BalanceError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Invalid(String),
+}
+
+impl fmt::Display for BalanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BalanceError::Io(error) => write!(f, "could not read the file: {error}"),
+            BalanceError::Parse(error) => write!(f, "{error}"),
+            BalanceError::Invalid(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for BalanceError {}
+
+impl From<std::io::Error> for BalanceError {
+    fn from(error: std::io::Error) -> Self {
+        BalanceError::Io(error)
+    }
+}
+
+impl From<ron::error::SpannedError> for BalanceError {
+    fn from(error: ron::error::SpannedError) -> Self {
+        BalanceError::Parse(error)
+    }
+}
+
+struct BalanceLoader;
+
+impl AssetLoader for BalanceLoader {
+    type Asset = Balance;
+    type Settings = ();
+    type Error = BalanceError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Balance, BalanceError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let balance: Balance = ron::de::from_bytes(&bytes)?;
+        balance.validate()?;
+        Ok(balance)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[BALANCE_EXTENSION]
+    }
+}
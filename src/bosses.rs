@@ -0,0 +1,245 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    collisions::check_enemy_health,
+    components::Health,
+    enemies::{Enemy, EnemyDestroyedEvent},
+    enemy_defs::EnemyDefs,
+    enemy_movement::{EnemyMovement, MovementProgress},
+    enemy_weapons::{EnemyWeapon, WeaponDef},
+    explosion::{DestroyedData, DestroyedEvent},
+    game::{despawn_all, GameplaySet, StartRun, StartRunSet},
+    game_state::GameState,
+    AppState,
+};
+
+/// Seconds between the explosions of a destroyed boss
+const EXPLOSION_INTERVAL: f32 = 0.12;
+
+/// Enemies with a `boss` section in their file. They change how they move and
+/// shoot as they lose health, and go out in a string of explosions.
+pub struct BossesPlugin;
+impl Plugin for BossesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            StartRun,
+            despawn_all::<ExplosionSequence>.in_set(StartRunSet::Despawn),
+        )
+        .add_systems(
+            FixedUpdate,
+            update_boss_phases
+                .after(check_enemy_health)
+                .in_set(GameplaySet::Resolve)
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            Update,
+            (start_explosion_sequences, run_explosion_sequences)
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// The boss section of an enemy file
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct BossDef {
+    /// Where bullets hit it. Bullets elsewhere in its sprite fly past.
+    pub hit_zones: Vec<HitZone>,
+    /// In order of decreasing `health`, starting with the one at full health
+    pub phases: Vec<BossPhase>,
+    /// How many explosions go off when it is destroyed
+    pub explosions: u32,
+}
+
+impl BossDef {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.hit_zones.is_empty() {
+            return Err("hit_zones must not be empty".into());
+        }
+        if let Some(zone) = self
+            .hit_zones
+            .iter()
+            .find(|zone| !(zone.size.cmpgt(Vec2::ZERO).all() && zone.offset.is_finite()))
+        {
+            return Err(format!(
+                "hit_zones sizes must be above zero, not {}",
+                zone.size
+            ));
+        }
+        if let Some(zone) = self.hit_zones.iter().find(|zone| zone.damage <= 0) {
+            return Err(format!(
+                "hit_zones damage must be above zero, not {}",
+                zone.damage
+            ));
+        }
+
+        match self.phases.first() {
+            None => return Err("phases must not be empty".into()),
+            Some(phase) if phase.health != 1.0 => {
+                return Err(format!(
+                    "the first phase must start at health 1.0, not {}",
+                    phase.health
+                ));
+            }
+            Some(_) => (),
+        }
+        if self
+            .phases
+            .windows(2)
+            .any(|pair| !(0.0..pair[0].health).contains(&pair[1].health))
+        {
+            return Err("phases health must go down from 1.0 and stay above 0.0".into());
+        }
+        for phase in &self.phases {
+            phase.movement.validate()?;
+            if let Some(weapon) = &phase.weapon {
+                weapon
+                    .validate()
+                    .map_err(|message| format!("phases weapon.{message}"))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A part of a boss that bullets hit, relative to its center
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct HitZone {
+    pub offset: Vec2,
+    pub size: Vec2,
+    /// Damage of a bullet hitting it
+    pub damage: i32,
+}
+
+/// How a boss fights once its health drops to `health`, from 1.0 for full
+/// health down towards 0.0
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct BossPhase {
+    pub health: f32,
+    pub movement: EnemyMovement,
+    #[serde(default)]
+    pub weapon: Option<WeaponDef>,
+}
+
+#[derive(Component, Default)]
+pub struct Boss {
+    /// The most health it has had, which is what it started with
+    pub max_health: i32,
+    /// Index into the phases of its `BossDef`, once the first has started
+    phase: Option<usize>,
+}
+
+#[derive(Component)]
+pub struct HitZones(pub Vec<HitZone>);
+
+/// Explosions going off one after the other over a destroyed boss
+#[derive(Component)]
+struct ExplosionSequence {
+    position: Vec3,
+    radius: f32,
+    count: u32,
+    fired: u32,
+    timer: Timer,
+}
+
+/// Switch bosses to the phase for their health, starting with the first
+fn update_boss_phases(
+    mut commands: Commands,
+    enemy_defs: Res<EnemyDefs>,
+    mut query: Query<(
+        Entity,
+        &Enemy,
+        &Health,
+        &mut EnemyMovement,
+        &mut MovementProgress,
+        &mut Boss,
+    )>,
+) {
+    for (entity, enemy, health, mut movement, mut progress, mut boss) in &mut query {
+        let Some(def) = &enemy_defs.get(enemy.enemy_type).boss else {
+            continue;
+        };
+        boss.max_health = boss.max_health.max(health.0);
+
+        let fraction = health.0 as f32 / boss.max_health as f32;
+        let phase = def
+            .phases
+            .iter()
+            .rposition(|phase| fraction <= phase.health)
+            .unwrap_or(0);
+        if boss.phase == Some(phase) {
+            continue;
+        }
+        boss.phase = Some(phase);
+
+        let phase = &def.phases[phase];
+        // Keep the spawn origin, so the boss patrols around the same spot
+        *movement = phase.movement.clone();
+        progress.restart();
+        // `try_insert`, as the boss may have been destroyed in the same tick
+        let mut entity = commands.entity(entity);
+        match &phase.weapon {
+            Some(weapon) => entity.try_insert(EnemyWeapon::new(*weapon)),
+            None => entity.remove::<EnemyWeapon>(),
+        };
+    }
+}
+
+fn start_explosion_sequences(
+    mut commands: Commands,
+    enemy_defs: Res<EnemyDefs>,
+    mut enemy_destroyed_event: EventReader<EnemyDestroyedEvent>,
+) {
+    for event in enemy_destroyed_event.read() {
+        let def = enemy_defs.get(event.0.enemy_type);
+        let Some(boss) = &def.boss else {
+            continue;
+        };
+        commands.spawn((
+            ExplosionSequence {
+                position: event.0.position,
+                radius: def.size().min_element() / 2.0,
+                count: boss.explosions,
+                fired: 0,
+                timer: Timer::from_seconds(EXPLOSION_INTERVAL, TimerMode::Repeating),
+            },
+            StateScoped(AppState::Game),
+        ));
+    }
+}
+
+/// Set off the explosions of each sequence in a spiral out from the middle
+fn run_explosion_sequences(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut ExplosionSequence)>,
+    mut destroyed_event: EventWriter<DestroyedEvent>,
+) {
+    for (entity, mut sequence) in &mut query {
+        sequence.timer.tick(time.delta());
+        for _ in 0..sequence.timer.times_finished_this_tick() {
+            if sequence.fired == sequence.count {
+                break;
+            }
+            let progress = (sequence.fired + 1) as f32 / sequence.count as f32;
+            // Golden angle, so the explosions never line up
+            let angle = sequence.fired as f32 * TAU * 0.382;
+            let offset = Vec2::from_angle(angle) * sequence.radius * progress.sqrt();
+            destroyed_event.send(DestroyedEvent(DestroyedData {
+                position: sequence.position + offset.extend(1.0),
+            }));
+            sequence.fired += 1;
+        }
+        if sequence.fired == sequence.count {
+            commands.entity(entity).despawn();
+        }
+    }
+}
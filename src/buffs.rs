@@ -0,0 +1,145 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{balance::Balance, components::PlayerStats, player::Player};
+
+/// The player stats that powerups boost for a while
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuffKind {
+    FireRate,
+    Speed,
+}
+
+/// How strong a buff is, how long it lasts and what picking it up again does
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct BuffDef {
+    /// Multiplies the stat for each stack
+    pub multiplier: f32,
+    /// Seconds it lasts after the last pickup
+    pub duration: f32,
+    pub stacking: Stacking,
+}
+
+impl BuffDef {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.multiplier.is_finite() || self.multiplier <= 0.0 {
+            return Err(format!(
+                "multiplier must be above zero, not {}",
+                self.multiplier
+            ));
+        }
+        if !self.duration.is_finite() || self.duration <= 0.0 {
+            return Err(format!(
+                "duration must be above zero, not {}",
+                self.duration
+            ));
+        }
+        match self.stacking {
+            Stacking::Stack { max: 0 } => Err("stacking max must be above zero".into()),
+            Stacking::Cap { max } if !max.is_finite() || max < 1.0 => {
+                Err(format!("stacking max must be at least 1.0, not {max}"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// How much the stat is multiplied by at `stacks`
+    fn strength(&self, stacks: u32) -> f32 {
+        let strength = self.multiplier.powi(stacks as i32);
+        match self.stacking {
+            Stacking::Cap { max } => strength.min(max),
+            _ => strength,
+        }
+    }
+
+    /// Whether another pickup would make it any stronger
+    fn is_maxed(&self, stacks: u32) -> bool {
+        match self.stacking {
+            Stacking::Refresh => false,
+            Stacking::Stack { max } => stacks >= max,
+            Stacking::Cap { max } => self.strength(stacks) >= max,
+        }
+    }
+}
+
+/// What picking up a buff that is still active does
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Stacking {
+    /// Start its duration over
+    Refresh,
+    /// Add a stack, up to `max` stacks, and start its duration over
+    Stack { max: u32 },
+    /// Add a stack until the multiplier reaches `max`, and start its duration
+    /// over
+    Cap { max: f32 },
+}
+
+#[derive(Debug)]
+pub struct ActiveBuff {
+    pub kind: BuffKind,
+    pub stacks: u32,
+    pub timer: Timer,
+}
+
+/// The buffs on the player. `PlayerStats` is worked out from these every tick.
+#[derive(Component, Default, Debug)]
+pub struct ActiveBuffs(pub Vec<ActiveBuff>);
+
+impl ActiveBuffs {
+    /// Apply a pickup of `kind`. Returns false if the buff was already as
+    /// strong as it gets, in which case only its duration starts over.
+    pub fn add(&mut self, kind: BuffKind, def: &BuffDef) -> bool {
+        let timer = Timer::from_seconds(def.duration, TimerMode::Once);
+        let Some(buff) = self.0.iter_mut().find(|buff| buff.kind == kind) else {
+            self.0.push(ActiveBuff {
+                kind,
+                stacks: 1,
+                timer,
+            });
+            return true;
+        };
+
+        buff.timer = timer;
+        if def.is_maxed(buff.stacks) {
+            return matches!(def.stacking, Stacking::Refresh);
+        }
+        if !matches!(def.stacking, Stacking::Refresh) {
+            buff.stacks += 1;
+        }
+        true
+    }
+
+    /// Seconds left on the buff of `kind`, if it is active
+    pub fn remaining(&self, kind: BuffKind) -> Option<f32> {
+        self.0
+            .iter()
+            .find(|buff| buff.kind == kind)
+            .map(|buff| buff.timer.remaining_secs())
+    }
+}
+
+/// Run down the player's buffs, drop the ones that ran out and work out the
+/// player's stats from the base values and what is left
+pub fn update_buffs(
+    time: Res<Time>,
+    balance: Res<Balance>,
+    mut query: Query<(&mut ActiveBuffs, &mut PlayerStats), With<Player>>,
+) {
+    for (mut buffs, mut stats) in &mut query {
+        for buff in &mut buffs.0 {
+            buff.timer.tick(time.delta());
+        }
+        buffs.0.retain(|buff| !buff.timer.finished());
+
+        let mut new_stats = PlayerStats::default();
+        for buff in &buffs.0 {
+            let strength = balance.buff(buff.kind).strength(buff.stacks);
+            match buff.kind {
+                BuffKind::FireRate => new_stats.fire_rate *= strength,
+                BuffKind::Speed => new_stats.speed *= strength,
+            }
+        }
+        *stats = new_stats;
+    }
+}
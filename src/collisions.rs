@@ -4,12 +4,16 @@ use bevy::{
 };
 
 use crate::{
+    balance::Balance,
+    bosses::HitZones,
     components::{Bounds, Bullet, Health},
-    enemies::{Enemy, EnemyCount, EnemyDestroyedData, EnemyDestroyedEvent, EnemyType},
+    enemies::{Enemy, EnemyCount, EnemyDestroyedData, EnemyDestroyedEvent},
+    enemy_defs::{EnemyDefs, EnemyType},
+    enemy_weapons::EnemyProjectile,
     explosion::{DestroyedData, DestroyedEvent},
+    game::{clear_events, GameplaySet, Score, StartRun, StartRunSet},
     game_state::GameState,
-    player::Player,
-    scoreboard::Score,
+    player::{apply_player_damage, Invincible, Player},
 };
 
 pub struct CollisionsPlugin;
@@ -17,17 +21,33 @@ impl Plugin for CollisionsPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<CollisionEvent>()
             .add_event::<EnemyDestroyedEvent>()
+            .add_event::<PlayerDamagedEvent>()
+            .add_event::<PlayerHurtEvent>()
             .add_systems(
-                Update,
-                ((
+                StartRun,
+                (
+                    clear_events::<CollisionEvent>,
+                    clear_events::<EnemyDestroyedEvent>,
+                    clear_events::<PlayerDamagedEvent>,
+                    clear_events::<PlayerHurtEvent>,
+                )
+                    .in_set(StartRunSet::Reset),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
                     (
                         check_player_bullet_enemy_collision,
                         check_player_enemy_collision,
+                        check_enemy_projectile_player_collision,
                     )
-                        .chain(),
-                    check_enemy_health,
+                        .chain()
+                        .in_set(GameplaySet::Collide),
+                    (apply_player_damage, check_enemy_health)
+                        .chain()
+                        .in_set(GameplaySet::Resolve),
                 )
-                    .run_if(in_state(GameState::Playing)),),
+                    .run_if(in_state(GameState::Playing)),
             );
     }
 }
@@ -38,6 +58,33 @@ pub struct Collider;
 #[derive(Event, Default)]
 pub struct CollisionEvent;
 
+/// The player got hit. Sent by the collision checks and applied by the
+/// player, unless it is still invincible from the last hit.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlayerDamagedEvent {
+    pub amount: i32,
+    pub source: DamageSource,
+    /// Which way the hit pushes the player
+    pub direction: Vec2,
+}
+
+/// A hit that got through and took the player's health, at most one per
+/// invincibility. The HUD and sounds react to these rather than to every
+/// [`PlayerDamagedEvent`].
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlayerHurtEvent {
+    pub amount: i32,
+    pub source: DamageSource,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DamageSource {
+    /// Flying into an enemy of this type
+    Enemy(EnemyType),
+    /// An enemy projectile
+    Projectile,
+}
+
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 enum Collision {
     Left,
@@ -46,36 +93,90 @@ enum Collision {
     Bottom,
 }
 
+impl Collision {
+    /// Away from the other entity, through the side they touched
+    fn direction(self) -> Vec2 {
+        match self {
+            Collision::Left => Vec2::NEG_X,
+            Collision::Right => Vec2::X,
+            Collision::Top => Vec2::Y,
+            Collision::Bottom => Vec2::NEG_Y,
+        }
+    }
+}
+
+/// Where an enemy can be hit, with the damage a bullet does there: its hit
+/// zones if it has any, or else all of its bounds
+fn hit_areas(
+    transform: &Transform,
+    bounds: &Bounds,
+    zones: Option<&HitZones>,
+) -> Vec<(Aabb2d, i32)> {
+    let center = transform.translation.truncate();
+    match zones {
+        Some(zones) => zones
+            .0
+            .iter()
+            .map(|zone| {
+                (
+                    Aabb2d::new(center + zone.offset, zone.size / 2.0),
+                    zone.damage,
+                )
+            })
+            .collect(),
+        None => vec![(Aabb2d::new(center, bounds.size / 2.0), 1)],
+    }
+}
+
+/// An enemy's position, size, hit zones and health, for working out where it
+/// was hit and how hard
+type EnemyTarget<'a> = (
+    &'a Transform,
+    &'a Bounds,
+    Option<&'a HitZones>,
+    &'a mut Health,
+);
+
+/// The player's position, size, health and whether it was just hit
+type PlayerTarget<'a> = (&'a Transform, &'a Bounds, &'a Health, Has<Invincible>);
+
 pub fn check_player_enemy_collision(
-    player: Single<(&Transform, &Bounds, &mut Health), With<Player>>,
-    mut enemy_query: Query<(&Transform, &Bounds, &mut Health), (With<Enemy>, Without<Player>)>,
+    player: Single<PlayerTarget, With<Player>>,
+    mut enemy_query: Query<(&Enemy, EnemyTarget), Without<Player>>,
     mut collision_events: EventWriter<CollisionEvent>,
+    mut damage_events: EventWriter<PlayerDamagedEvent>,
+    enemy_defs: Res<EnemyDefs>,
+    balance: Res<Balance>,
 ) {
-    let (player_transform, player_bounds, mut player_health) = player.into_inner();
+    let (player_transform, player_bounds, player_health, mut invincible) = player.into_inner();
     let player_aabb2d = Aabb2d::new(
         player_transform.translation.truncate(),
         player_bounds.size / 2.0,
     );
 
-    for (enemy_transform, enemy_bounds, mut enemy_health) in &mut enemy_query {
-        let collision = is_collision(
-            player_aabb2d,
-            Aabb2d::new(
-                enemy_transform.translation.truncate(),
-                enemy_bounds.size / 2.0,
-            ),
-        );
+    for (enemy, (enemy_transform, enemy_bounds, hit_zones, mut enemy_health)) in &mut enemy_query {
+        // Running into the edge of two zones hits the one it hurts most
+        let hit = hit_areas(enemy_transform, enemy_bounds, hit_zones)
+            .into_iter()
+            .filter_map(|(area, damage)| {
+                is_collision(player_aabb2d, area).map(|collision| (collision, damage))
+            })
+            .max_by_key(|(_, damage)| *damage);
 
-        if let Some(_collision) = collision {
+        if let Some((collision, damage)) = hit {
             // Sends a collision event so that other systems can react to the collision
             collision_events.send_default();
 
-            if enemy_health.0 > 0 && player_health.0 > 0 {
-                let new_player_health = player_health.0 - enemy_health.0;
-                let new_enemy_health = enemy_health.0 - player_health.0;
-
-                player_health.0 = new_player_health;
-                enemy_health.0 = new_enemy_health;
+            // Both get hurt, unless the player was just hit
+            if !invincible && enemy_health.0 > 0 && player_health.0 > 0 {
+                let contact_damage = enemy_defs.get(enemy.enemy_type).contact_damage;
+                damage_events.send(PlayerDamagedEvent {
+                    amount: contact_damage.min(enemy_health.0),
+                    source: DamageSource::Enemy(enemy.enemy_type),
+                    direction: collision.direction(),
+                });
+                enemy_health.0 -= balance.ramming_damage * damage;
+                invincible = true;
             }
         }
     }
@@ -83,22 +184,23 @@ pub fn check_player_enemy_collision(
 
 fn check_player_bullet_enemy_collision(
     mut commands: Commands,
-    mut enemy_query: Query<(&Transform, &Bounds, &mut Health, &Collider), With<Enemy>>,
+    mut enemy_query: Query<EnemyTarget, (With<Enemy>, With<Collider>)>,
     bullet_query: Query<(Entity, &Transform), With<Bullet>>,
 ) {
     let player_damage = 1;
 
-    for (enemy_transform, enemy_bounds, mut enemy_health, _) in &mut enemy_query {
+    for (enemy_transform, enemy_bounds, hit_zones, mut enemy_health) in &mut enemy_query {
+        let areas = hit_areas(enemy_transform, enemy_bounds, hit_zones);
         for (bullet_entity, bullet_transform) in &bullet_query {
-            let collision = is_collision(
-                Aabb2d::new(
-                    enemy_transform.translation.truncate(),
-                    enemy_bounds.size / 2.0,
-                ),
-                Aabb2d::new(bullet_transform.translation.truncate(), Vec2::splat(8.0)),
-            );
-            if let Some(_collision) = collision {
-                let new_health = enemy_health.0 - player_damage;
+            let bullet = Aabb2d::new(bullet_transform.translation.truncate(), Vec2::splat(8.0));
+            // A bullet on the edge of two zones hits the one it hurts most
+            let damage = areas
+                .iter()
+                .filter(|(area, _)| is_collision(*area, bullet).is_some())
+                .map(|(_, damage)| *damage)
+                .max();
+            if let Some(damage) = damage {
+                let new_health = enemy_health.0 - player_damage * damage;
                 enemy_health.0 = new_health;
 
                 commands.entity(bullet_entity).despawn();
@@ -107,6 +209,46 @@ fn check_player_bullet_enemy_collision(
     }
 }
 
+pub fn check_enemy_projectile_player_collision(
+    mut commands: Commands,
+    player: Single<(&Transform, &Bounds, Has<Invincible>), With<Player>>,
+    projectile_query: Query<(Entity, &Transform, &EnemyProjectile)>,
+    mut collision_events: EventWriter<CollisionEvent>,
+    mut damage_events: EventWriter<PlayerDamagedEvent>,
+) {
+    let (player_transform, player_bounds, invincible) = player.into_inner();
+    // Projectiles fly through the player while it is invincible
+    if invincible {
+        return;
+    }
+    let player_aabb2d = Aabb2d::new(
+        player_transform.translation.truncate(),
+        player_bounds.size / 2.0,
+    );
+
+    for (projectile_entity, projectile_transform, projectile) in &projectile_query {
+        let collision = is_collision(
+            player_aabb2d,
+            Aabb2d::new(
+                projectile_transform.translation.truncate(),
+                Vec2::splat(4.0),
+            ),
+        );
+        if let Some(collision) = collision {
+            collision_events.send_default();
+            damage_events.send(PlayerDamagedEvent {
+                amount: projectile.damage,
+                source: DamageSource::Projectile,
+                direction: collision.direction(),
+            });
+
+            commands.entity(projectile_entity).despawn();
+            // The first hit makes the player invincible to the rest
+            break;
+        }
+    }
+}
+
 fn is_collision(entity_a: Aabb2d, entity_b: Aabb2d) -> Option<Collision> {
     if !entity_a.intersects(&entity_b) {
         return None;
@@ -129,10 +271,11 @@ fn is_collision(entity_a: Aabb2d, entity_b: Aabb2d) -> Option<Collision> {
     Some(side)
 }
 
-fn check_enemy_health(
+pub fn check_enemy_health(
     mut commands: Commands,
     enemy_query: Query<(Entity, &Transform, &Enemy, &Health), With<Enemy>>,
     mut enemy_count: ResMut<EnemyCount>,
+    enemy_defs: Res<EnemyDefs>,
     mut score: ResMut<Score>,
     mut destroyed_event: EventWriter<DestroyedEvent>,
     mut enemy_destroyed_event: EventWriter<EnemyDestroyedEvent>,
@@ -142,11 +285,7 @@ fn check_enemy_health(
             commands.entity(enemy_entity).despawn();
             enemy_count.decrement(&enemy.enemy_type);
 
-            **score += match enemy.enemy_type {
-                EnemyType::Large => 40,
-                EnemyType::Medium => 12,
-                EnemyType::Small => 2,
-            };
+            **score += enemy_defs.get(enemy.enemy_type).score;
 
             enemy_destroyed_event.send(EnemyDestroyedEvent(EnemyDestroyedData {
                 enemy_type: enemy.enemy_type,
@@ -55,8 +55,38 @@ impl Shoot {
     }
 }
 
+/// A player bullet, flying at `velocity` in world units per second
 #[derive(Component)]
-pub struct Bullet;
+pub struct Bullet {
+    pub velocity: Vec2,
+}
+
+/// How many bolts the player's gun fires at once, from 1 up to
+/// [`WeaponLevel::MAX`]. Weapon upgrades raise it and taking damage lowers it.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WeaponLevel(pub u8);
+
+impl WeaponLevel {
+    pub const MAX: u8 = 4;
+
+    pub fn upgrade(&mut self) {
+        self.0 = (self.0 + 1).min(Self::MAX);
+    }
+
+    pub fn downgrade(&mut self) {
+        self.0 = self.0.saturating_sub(1).max(1);
+    }
+}
+
+impl Default for WeaponLevel {
+    fn default() -> Self {
+        Self(1)
+    }
+}
 
 #[derive(Component)]
 pub struct Health(pub i32);
+
+/// The most health that healing can bring an entity back up to
+#[derive(Component)]
+pub struct MaxHealth(pub i32);
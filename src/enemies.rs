@@ -1,77 +1,26 @@
-use bevy::{
-    image::{ImageLoaderSettings, ImageSampler},
-    prelude::*,
-};
-use bevy_rand::prelude::*;
-use rand::prelude::*;
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use rand::{distributions::WeightedIndex, prelude::*};
 
 use crate::{
+    balance::Balance,
+    bosses::{Boss, BossesPlugin, HitZones},
     collisions::Collider,
     components::{Bounds, Health, MovementSpeed},
-    game::GameRestartEvent,
+    enemy_defs::{EnemyDefs, EnemyDefsPlugin, EnemyType},
+    enemy_movement::{apply_enemy_movement, EnemyMovement, MovementProgress},
+    enemy_weapons::{EnemyWeapon, EnemyWeaponsPlugin},
+    game::{despawn_all, GameplaySet, StartRun, StartRunSet},
     game_state::GameState,
+    interpolation::InterpolatedTranslation,
+    levels::random_spawns_active,
+    play_area::PlayArea,
+    rng::EnemySpawnRng,
     sprite_animation::{update_animations, AnimationConfig},
     AppState,
 };
 
-struct EnemyConfig {
-    sprite_path: &'static str,
-    sprite_size: UVec2,
-    sprite_columns: u32,
-    sprite_rows: u32,
-    sprite_fps: u8,
-    speed: f32,
-    scale: f32,
-    health: i32,
-    spawn_weight: f32,
-}
-
-#[derive(Component, Clone, Copy, Debug)]
-pub enum EnemyType {
-    Small,
-    Medium,
-    Large,
-}
-impl EnemyType {
-    fn config(&self) -> EnemyConfig {
-        match self {
-            EnemyType::Small => EnemyConfig {
-                sprite_path: "../assets/enemy-small.png",
-                sprite_size: UVec2::new(17, 16),
-                sprite_columns: 2,
-                sprite_rows: 1,
-                sprite_fps: 12,
-                speed: 100.0,
-                scale: 2.0,
-                health: 2,
-                spawn_weight: 8.0,
-            },
-            EnemyType::Medium => EnemyConfig {
-                sprite_path: "../assets/enemy-medium.png",
-                sprite_size: UVec2::new(32, 16),
-                sprite_columns: 2,
-                sprite_rows: 1,
-                sprite_fps: 12,
-                speed: 50.0,
-                scale: 2.0,
-                health: 8,
-                spawn_weight: 0.4,
-            },
-            EnemyType::Large => EnemyConfig {
-                sprite_path: "../assets/enemy-large.png",
-                sprite_size: UVec2::new(32, 32),
-                sprite_columns: 2,
-                sprite_rows: 1,
-                sprite_fps: 12,
-                speed: 25.0,
-                scale: 2.0,
-                health: 20,
-                spawn_weight: 0.1,
-            },
-        }
-    }
-}
-
 pub struct EnemyDestroyedData {
     pub enemy_type: EnemyType,
     pub position: Vec3,
@@ -80,36 +29,37 @@ pub struct EnemyDestroyedData {
 #[derive(Event)]
 pub struct EnemyDestroyedEvent(pub EnemyDestroyedData);
 
-const MAX_ENEMIES: usize = 40;
-const ENEMY_SPAWN_CHANCE: u32 = 1;
-const ENEMY_SPAWN_DENOMINATOR: u32 = 100; // higher means less enemies
 const ENEMY_GUTTER: f32 = 4.0;
 
+/// Whether enemies spawn on their own, from the stages' waves and the random
+/// spawner. Turned off for scripted scenarios.
+#[derive(Resource)]
+pub struct EnemySpawning(pub bool);
+
+impl Default for EnemySpawning {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
 // This resource tracks the count of each enemy type
 #[derive(Resource, Default)]
-pub struct EnemyCount {
-    pub small: usize,
-    pub medium: usize,
-    pub large: usize,
-}
+pub struct EnemyCount(pub BTreeMap<EnemyType, usize>);
 impl EnemyCount {
     pub fn total(&self) -> usize {
-        self.small + self.medium + self.large
+        self.0.values().sum()
     }
 
     pub fn increment(&mut self, enemy_type: &EnemyType) {
-        match enemy_type {
-            EnemyType::Small => self.small += 1,
-            EnemyType::Medium => self.medium += 1,
-            EnemyType::Large => self.large += 1,
-        }
+        *self.0.entry(*enemy_type).or_default() += 1;
     }
 
     pub fn decrement(&mut self, enemy_type: &EnemyType) {
-        match enemy_type {
-            EnemyType::Small => self.small = self.small.saturating_sub(1),
-            EnemyType::Medium => self.medium = self.medium.saturating_sub(1),
-            EnemyType::Large => self.large = self.large.saturating_sub(1),
+        if let Some(count) = self.0.get_mut(enemy_type) {
+            *count -= 1;
+            if *count == 0 {
+                self.0.remove(enemy_type);
+            }
         }
     }
 }
@@ -118,21 +68,34 @@ pub struct EnemiesPlugin;
 
 impl Plugin for EnemiesPlugin {
     fn build(&self, app: &mut App) {
-        app
+        app.add_plugins((EnemyDefsPlugin, EnemyWeaponsPlugin, BossesPlugin))
             //.add_systems(Startup, spawn_enemies)
             .init_resource::<EnemyCount>()
-            .add_systems(OnEnter(AppState::Game), enemies_setup)
+            .init_resource::<EnemySpawning>()
             .add_systems(
-                Update,
+                StartRun,
                 (
-                    spawn_enemies,
-                    apply_enemy_movement,
-                    remove_fallen_enemies,
-                    update_animations::<Enemy>,
+                    despawn_all::<Enemy>.in_set(StartRunSet::Despawn),
+                    reset_enemy_count.in_set(StartRunSet::Reset),
+                ),
+            )
+            .add_systems(OnExit(AppState::Game), reset_enemy_count)
+            .add_systems(
+                FixedUpdate,
+                (
+                    spawn_enemies
+                        .run_if(enemy_spawning_enabled.and(random_spawns_active))
+                        .in_set(GameplaySet::Spawn),
+                    (apply_enemy_movement, remove_fallen_enemies)
+                        .chain()
+                        .in_set(GameplaySet::Move),
                 )
-                    .run_if(in_state(AppState::Game).and(in_state(GameState::Playing))),
+                    .run_if(in_state(GameState::Playing)),
             )
-            .add_systems(Update, reset_enemies);
+            .add_systems(
+                Update,
+                update_animations::<Enemy>.run_if(in_state(GameState::Playing)),
+            );
     }
 }
 
@@ -142,109 +105,106 @@ pub struct Enemy {
     pub enemy_type: EnemyType,
 }
 
-fn enemies_setup(mut enemy_count: ResMut<EnemyCount>) {
-    // Reset all counts to 0 when entering Playing state
+fn reset_enemy_count(mut enemy_count: ResMut<EnemyCount>) {
+    // Reset all counts to 0 when a game starts or ends
     *enemy_count = EnemyCount::default();
 }
 
-fn spawn_enemies(
+pub fn enemy_spawning_enabled(enemy_spawning: Res<EnemySpawning>) -> bool {
+    enemy_spawning.0
+}
+
+/// The random spawner, for the waves that have it running
+pub fn spawn_enemies(
     mut commands: Commands,
     mut enemy_count: ResMut<EnemyCount>,
-    mut rng: ResMut<GlobalEntropy<WyRand>>,
-    window: Single<&Window>,
-    asset_server: Res<AssetServer>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut rng: ResMut<EnemySpawnRng>,
+    play_area: Res<PlayArea>,
+    enemy_defs: Res<EnemyDefs>,
+    balance: Res<Balance>,
 ) {
     // Only spawn new enemies if we haven't reached the maximum
-    if enemy_count.total() >= MAX_ENEMIES {
+    if enemy_count.total() >= balance.max_enemies {
         return;
     }
 
     // Random chance to spawn a new enemy
-    if rng.gen_range(0..ENEMY_SPAWN_DENOMINATOR) > ENEMY_SPAWN_CHANCE {
+    if rng.gen_range(0..balance.enemy_spawn_denominator) > balance.enemy_spawn_chance {
         return;
     }
 
-    let enemy_type = {
-        let weights = [
-            (EnemyType::Large, EnemyType::Large.config().spawn_weight),
-            (EnemyType::Medium, EnemyType::Medium.config().spawn_weight),
-            (EnemyType::Small, EnemyType::Small.config().spawn_weight),
-        ];
-        let total_weight: f32 = weights.iter().map(|(_, w)| w).sum();
-        let roll = rng.gen::<f32>() * total_weight;
-
-        let mut selected = EnemyType::Small;
-        for (enemy_type, weight) in weights.iter() {
-            if roll <= *weight {
-                selected = *enemy_type;
-                break;
-            }
-        }
-        selected
+    let Ok(weights) = WeightedIndex::new(enemy_defs.iter().map(|(_, def)| def.spawn_weight)) else {
+        // No enemies are defined, or none of them spawn on their own
+        return;
+    };
+    let (enemy_type, def) = enemy_defs.iter().nth(rng.sample(&weights)).unwrap();
+    let movement = match rng.gen_bool(0.5) {
+        true => def.movement.mirrored(),
+        false => def.movement.clone(),
     };
 
-    let config = enemy_type.config();
-    let size = config.sprite_size.as_vec2();
-    let size_x = size.x * config.scale;
-    let column_count = (window.width() / (size_x + ENEMY_GUTTER)) as u32;
-    let column = rng.gen_range(0..column_count);
-    let x_pos = calculate_enemy_x_position(&window, column, size_x);
-    let spawn_position = Vec3::new(x_pos, window.height() / 2.0 + size_x / 2.0, 1.0);
-
-    let texture = asset_server.load_with_settings(
-        config.sprite_path,
-        |settings: &mut ImageLoaderSettings| {
-            settings.sampler = ImageSampler::nearest();
-        },
-    );
-    let layout = TextureAtlasLayout::from_grid(
-        config.sprite_size,
-        config.sprite_columns,
-        config.sprite_rows,
-        None,
-        None,
+    let size_x = def.size().x;
+    let column = rng.gen_range(0..column_count(&play_area, size_x));
+    let x_pos = calculate_enemy_x_position(&play_area, column, size_x);
+    let spawn_position = Vec3::new(x_pos, play_area.height() / 2.0 + size_x / 2.0, 1.0);
+
+    spawn_enemy(
+        &mut commands,
+        &enemy_defs,
+        &mut enemy_count,
+        enemy_type,
+        spawn_position,
+        movement,
     );
-    let texture_atlas_layout = texture_atlas_layouts.add(layout);
+}
 
-    commands.spawn((
+/// Spawn an enemy of the given type centered on `position`, moving the way
+/// its file says or along any other `movement`
+pub fn spawn_enemy(
+    commands: &mut Commands,
+    enemy_defs: &EnemyDefs,
+    enemy_count: &mut EnemyCount,
+    enemy_type: EnemyType,
+    position: Vec3,
+    movement: EnemyMovement,
+) -> Entity {
+    let def = enemy_defs.get(enemy_type);
+
+    let mut entity = commands.spawn((
         Enemy { enemy_type },
+        StateScoped(AppState::Game),
         Collider,
-        Transform::from_translation(spawn_position),
-        MovementSpeed(config.speed),
-        Health(config.health),
-        Bounds {
-            size: size * config.scale,
-        },
-        Sprite {
-            image: texture,
-            texture_atlas: Some(TextureAtlas {
-                layout: texture_atlas_layout,
-                index: 0,
-            }),
-            custom_size: Some(size * config.scale),
-            ..default()
-        },
-        AnimationConfig::new(0, 1, config.sprite_fps),
+        Transform::from_translation(position),
+        InterpolatedTranslation::new(position),
+        movement,
+        MovementProgress::new(position.truncate()),
+        MovementSpeed(def.speed),
+        Health(def.health),
+        Bounds { size: def.size() },
+        def.sheet.sprite(def.animation.first, def.scale),
+        AnimationConfig::new(def.animation.first, def.animation.last, def.animation.fps),
     ));
+    if let Some(weapon) = def.weapon {
+        entity.insert(EnemyWeapon::new(weapon));
+    }
+    if let Some(boss) = &def.boss {
+        entity.insert((Boss::default(), HitZones(boss.hit_zones.clone())));
+    }
 
     enemy_count.increment(&enemy_type);
+    entity.id()
 }
 
-fn apply_enemy_movement(
-    mut query: Populated<(&MovementSpeed, &mut Transform), With<Enemy>>,
-    time: Res<Time>,
-) {
-    for (speed, mut transform) in query.iter_mut() {
-        let movement = Vec2::new(0.0, -speed.0 * time.delta_secs());
-        transform.translation += movement.extend(0.0);
-    }
+/// How many enemies `size_x` wide fit side by side across the play area. An
+/// enemy wider than the play area still gets one column, in the middle.
+fn column_count(play_area: &PlayArea, size_x: f32) -> u32 {
+    ((play_area.width() / (size_x + ENEMY_GUTTER)) as u32).max(1)
 }
 
 /// Calculate the x position for an enemy in a given column
-fn calculate_enemy_x_position(window: &Window, column: u32, size_x: f32) -> f32 {
-    let width = window.width();
-    let column_count = (width / (size_x + ENEMY_GUTTER)) as u32;
+fn calculate_enemy_x_position(play_area: &PlayArea, column: u32, size_x: f32) -> f32 {
+    let width = play_area.width();
+    let column_count = column_count(play_area, size_x);
     let gutter_count = column_count - 1;
     let content_width = column_count as f32 * size_x + gutter_count as f32 * ENEMY_GUTTER;
     let margin = (width - content_width) / 2.0;
@@ -252,34 +212,23 @@ fn calculate_enemy_x_position(window: &Window, column: u32, size_x: f32) -> f32
     (column as f32 * (size_x + ENEMY_GUTTER)) + size_x / 2.0 + margin - width / 2.0
 }
 
+/// Remove enemies that fell past the bottom of the play area, flew off its
+/// sides or flew back out of its top
 fn remove_fallen_enemies(
     mut commands: Commands,
     mut enemy_count: ResMut<EnemyCount>,
-    query: Query<(Entity, &Transform, &Enemy), With<Enemy>>,
-    window: Single<&Window>,
+    query: Query<(Entity, &Transform, &Bounds, &MovementProgress, &Enemy)>,
+    play_area: Res<PlayArea>,
 ) {
-    for (entity, transform, enemy) in &query {
-        if transform.translation.y < -window.height() / 2.0 {
+    let half_area = play_area.half_size();
+    for (entity, transform, bounds, progress, enemy) in &query {
+        let position = transform.translation.truncate();
+        let fallen = position.y < -half_area.y;
+        let flown_off = position.x.abs() - bounds.size.x / 2.0 > half_area.x;
+        let retreated = progress.retreating && position.y - bounds.size.y / 2.0 > half_area.y;
+        if fallen || flown_off || retreated {
             commands.entity(entity).despawn();
             enemy_count.decrement(&enemy.enemy_type);
         }
     }
 }
-
-fn reset_enemies(
-    mut commands: Commands,
-    mut enemy_count: ResMut<EnemyCount>,
-    mut game_restart_event: EventReader<GameRestartEvent>,
-    query: Query<Entity, With<Enemy>>,
-) {
-    if !game_restart_event.is_empty() {
-        game_restart_event.clear();
-
-        for entity in &query {
-            commands.entity(entity).despawn();
-        }
-
-        // Reset all counts to 0
-        *enemy_count = EnemyCount::default();
-    }
-}
@@ -0,0 +1,419 @@
+use std::fmt;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    image::{ImageLoaderSettings, ImageSampler},
+    prelude::*,
+};
+use serde::Deserialize;
+
+use crate::{
+    bosses::BossDef, enemy_movement::EnemyMovement, enemy_weapons::WeaponDef,
+    loading::AssetManifest, powerups::PowerupType, sprite_assets::SpriteSheet, AppState,
+};
+
+/// Folder with one `<name>.enemy.ron` file per enemy archetype, relative to
+/// the `assets` folder
+const ENEMY_DEFS_FOLDER: &str = "enemies";
+const ENEMY_DEF_EXTENSION: &str = "enemy.ron";
+/// Names every file in [`ENEMY_DEFS_FOLDER`], as folders can't be listed on
+/// the web
+const ENEMY_LIST_PATH: &str = "enemies.manifest.ron";
+const ENEMY_LIST_EXTENSION: &str = "manifest.ron";
+
+/// Loads the enemy archetypes listed in `assets/enemies.manifest.ron` from
+/// `assets/enemies`, so new enemies can be added without touching the code
+pub struct EnemyDefsPlugin;
+impl Plugin for EnemyDefsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<EnemyDef>()
+            .init_asset::<EnemyList>()
+            .register_asset_loader(EnemyDefLoader)
+            .register_asset_loader(EnemyListLoader)
+            .init_resource::<EnemyDefs>()
+            .add_systems(OnEnter(AppState::Loading), add_enemy_defs_to_manifest)
+            .add_systems(OnExit(AppState::Loading), collect_enemy_defs);
+    }
+}
+
+/// An enemy archetype, as defined by one of the files in `assets/enemies`
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EnemyType(usize);
+
+impl EnemyType {
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+#[derive(Asset, TypePath, Clone)]
+pub struct EnemyDef {
+    /// File name without the extension, e.g. `small`
+    pub name: String,
+    #[dependency]
+    pub sheet: SpriteSheet,
+    pub animation: AnimationFrames,
+    pub scale: f32,
+    pub speed: f32,
+    pub health: i32,
+    /// The most damage running into it does to the player. It does no more
+    /// than the health it has left.
+    pub contact_damage: i32,
+    /// Added to the score when the enemy is destroyed
+    pub score: usize,
+    /// How likely this enemy is to be picked by the random spawner, relative
+    /// to the other enemies
+    pub spawn_weight: f32,
+    /// Tried in order when the enemy is destroyed, dropping at most one powerup
+    pub drops: Vec<Drop>,
+    pub movement: EnemyMovement,
+    /// Enemies without one only hurt the player by running into it
+    pub weapon: Option<WeaponDef>,
+    pub boss: Option<BossDef>,
+}
+
+impl EnemyDef {
+    /// Size of the enemy in the world
+    pub fn size(&self) -> Vec2 {
+        self.sheet.frame_size.as_vec2() * self.scale
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct AnimationFrames {
+    pub first: usize,
+    pub last: usize,
+    pub fps: u8,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Drop {
+    pub powerup: PowerupType,
+    /// From 0.0 for never to 1.0 for always
+    pub chance: f64,
+}
+
+/// Every enemy file to load, as listed in `assets/enemies.manifest.ron`
+#[derive(Asset, TypePath)]
+struct EnemyList {
+    #[dependency]
+    defs: Vec<Handle<EnemyDef>>,
+}
+
+/// Every enemy archetype, in file name order
+#[derive(Resource)]
+pub struct EnemyDefs {
+    list: Handle<EnemyList>,
+    defs: Vec<EnemyDef>,
+}
+
+impl EnemyDefs {
+    pub fn get(&self, enemy_type: EnemyType) -> &EnemyDef {
+        &self.defs[enemy_type.0]
+    }
+
+    /// The enemy defined in `assets/enemies/<name>.enemy.ron`
+    pub fn find(&self, name: &str) -> Option<EnemyType> {
+        self.iter()
+            .find_map(|(enemy_type, def)| (def.name == name).then_some(enemy_type))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (EnemyType, &EnemyDef)> {
+        self.defs
+            .iter()
+            .enumerate()
+            .map(|(index, def)| (EnemyType(index), def))
+    }
+}
+
+/// Path of the file defining the enemy called `name`, relative to the
+/// `assets` folder
+pub fn enemy_def_path(name: &str) -> String {
+    format!("{ENEMY_DEFS_FOLDER}/{name}.{ENEMY_DEF_EXTENSION}")
+}
+
+impl FromWorld for EnemyDefs {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            list: world.resource::<AssetServer>().load(ENEMY_LIST_PATH),
+            defs: Vec::new(),
+        }
+    }
+}
+
+/// The list and every file on it have to load for the game to start
+fn add_enemy_defs_to_manifest(mut manifest: ResMut<AssetManifest>, enemy_defs: Res<EnemyDefs>) {
+    manifest.add(enemy_defs.list.clone());
+}
+
+fn collect_enemy_defs(
+    mut enemy_defs: ResMut<EnemyDefs>,
+    lists: Res<Assets<EnemyList>>,
+    defs: Res<Assets<EnemyDef>>,
+) {
+    let Some(list) = lists.get(&enemy_defs.list) else {
+        return;
+    };
+
+    let mut collected: Vec<EnemyDef> = list
+        .defs
+        .iter()
+        .filter_map(|handle| defs.get(handle))
+        .cloned()
+        .collect();
+    collected.sort_by(|a, b| a.name.cmp(&b.name));
+
+    enemy_defs.defs = collected;
+}
+
+/// An enemy definition as written in its file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EnemyFile {
+    sprite: SheetFile,
+    animation: AnimationFrames,
+    scale: f32,
+    speed: f32,
+    health: i32,
+    contact_damage: i32,
+    score: usize,
+    spawn_weight: f32,
+    #[serde(default)]
+    drops: Vec<Drop>,
+    #[serde(default)]
+    movement: EnemyMovement,
+    #[serde(default)]
+    weapon: Option<WeaponDef>,
+    #[serde(default)]
+    boss: Option<BossDef>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SheetFile {
+    /// Relative to the `assets` folder
+    path: String,
+    frame_size: (u32, u32),
+    columns: u32,
+    rows: u32,
+}
+
+impl EnemyFile {
+    fn validate(&self) -> Result<(), EnemyDefError> {
+        let invalid = |message: String| Err(EnemyDefError::Invalid(message));
+        let sprite = &self.sprite;
+        let frame_count = (sprite.columns * sprite.rows) as usize;
+
+        if sprite.frame_size.0 == 0 || sprite.frame_size.1 == 0 {
+            return invalid("sprite.frame_size must not be zero".into());
+        }
+        if frame_count == 0 {
+            return invalid("sprite.columns and sprite.rows must not be zero".into());
+        }
+        if self.animation.first > self.animation.last {
+            return invalid(format!(
+                "animation.first ({}) comes after animation.last ({})",
+                self.animation.first, self.animation.last
+            ));
+        }
+        if self.animation.last >= frame_count {
+            return invalid(format!(
+                "animation.last ({}) is past the {frame_count} frames of {}",
+                self.animation.last, sprite.path
+            ));
+        }
+        if self.animation.fps == 0 {
+            return invalid("animation.fps must not be zero".into());
+        }
+        if self.scale.is_nan() || self.scale <= 0.0 {
+            return invalid(format!("scale must be above zero, not {}", self.scale));
+        }
+        if self.speed.is_nan() || self.speed < 0.0 {
+            return invalid(format!("speed must not be negative, not {}", self.speed));
+        }
+        if self.health <= 0 {
+            return invalid(format!("health must be above zero, not {}", self.health));
+        }
+        if self.contact_damage <= 0 {
+            return invalid(format!(
+                "contact_damage must be above zero, not {}",
+                self.contact_damage
+            ));
+        }
+        if !self.spawn_weight.is_finite() || self.spawn_weight < 0.0 {
+            return invalid(format!(
+                "spawn_weight must not be negative, not {}",
+                self.spawn_weight
+            ));
+        }
+        if let Some(drop) = self
+            .drops
+            .iter()
+            .find(|drop| !(0.0..=1.0).contains(&drop.chance))
+        {
+            return invalid(format!(
+                "drop chance must be between 0.0 and 1.0, not {}",
+                drop.chance
+            ));
+        }
+
+        self.movement.validate().map_err(EnemyDefError::Invalid)?;
+        if let Some(weapon) = &self.weapon {
+            weapon
+                .validate()
+                .map_err(|message| EnemyDefError::Invalid(format!("weapon.{message}")))?;
+        }
+        if let Some(boss) = &self.boss {
+            boss.validate()
+                .map_err(|message| EnemyDefError::Invalid(format!("boss.{message}")))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum EnemyDefError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Invalid(String),
+}
+
+impl fmt::Display for EnemyDefError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnemyDefError::Io(error) => write!(f, "could not read the file: {error}"),
+            EnemyDefError::Parse(error) => write!(f, "{error}"),
+            EnemyDefError::Invalid(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for EnemyDefError {}
+
+impl From<std::io::Error> for EnemyDefError {
+    fn from(error: std::io::Error) -> Self {
+        EnemyDefError::Io(error)
+    }
+}
+
+impl From<ron::error::SpannedError> for EnemyDefError {
+    fn from(error: ron::error::SpannedError) -> Self {
+        EnemyDefError::Parse(error)
+    }
+}
+
+struct EnemyDefLoader;
+
+impl AssetLoader for EnemyDefLoader {
+    type Asset = EnemyDef;
+    type Settings = ();
+    type Error = EnemyDefError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<EnemyDef, EnemyDefError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let file: EnemyFile = ron::de::from_bytes(&bytes)?;
+        file.validate()?;
+
+        let name = load_context
+            .path()
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(&format!(".{ENEMY_DEF_EXTENSION}")))
+            .unwrap_or_default()
+            .to_string();
+
+        let frame_size = UVec2::new(file.sprite.frame_size.0, file.sprite.frame_size.1);
+        let image = load_context
+            .loader()
+            .with_settings(|settings: &mut ImageLoaderSettings| {
+                settings.sampler = ImageSampler::nearest();
+            })
+            .load(file.sprite.path);
+        let layout = load_context.add_labeled_asset(
+            "layout".into(),
+            TextureAtlasLayout::from_grid(
+                frame_size,
+                file.sprite.columns,
+                file.sprite.rows,
+                None,
+                None,
+            ),
+        );
+
+        Ok(EnemyDef {
+            name,
+            sheet: SpriteSheet {
+                image,
+                layout,
+                frame_size,
+            },
+            animation: file.animation,
+            scale: file.scale,
+            speed: file.speed,
+            health: file.health,
+            contact_damage: file.contact_damage,
+            score: file.score,
+            spawn_weight: file.spawn_weight,
+            drops: file.drops,
+            movement: file.movement,
+            weapon: file.weapon,
+            boss: file.boss,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[ENEMY_DEF_EXTENSION]
+    }
+}
+
+struct EnemyListLoader;
+
+impl AssetLoader for EnemyListLoader {
+    type Asset = EnemyList;
+    type Settings = ();
+    type Error = EnemyDefError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<EnemyList, EnemyDefError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let mut names: Vec<String> = ron::de::from_bytes(&bytes)?;
+        if names.is_empty() {
+            return Err(EnemyDefError::Invalid(
+                "at least one enemy must be listed".into(),
+            ));
+        }
+        names.sort();
+        if let Some(name) = names.windows(2).find(|pair| pair[0] == pair[1]) {
+            return Err(EnemyDefError::Invalid(format!(
+                "{} is listed more than once",
+                name[0]
+            )));
+        }
+
+        // A listed file that is missing fails the list, and so the loading
+        Ok(EnemyList {
+            defs: names
+                .iter()
+                .map(|name| load_context.load(enemy_def_path(name)))
+                .collect(),
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[ENEMY_LIST_EXTENSION]
+    }
+}
@@ -0,0 +1,339 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{components::MovementSpeed, enemies::Enemy, player::Player};
+
+/// How an enemy moves. Distances are in world units and times in seconds, and
+/// enemies move at their `MovementSpeed` unless a behavior has its own speed.
+///
+/// Every behavior only depends on the enemy's spawn position, the time since
+/// it spawned and the player's position, so a run plays out the same way from
+/// the same seed and inputs.
+#[derive(Component, Deserialize, Clone, Debug, Default, PartialEq)]
+pub enum EnemyMovement {
+    /// Straight down
+    #[default]
+    Descend,
+    /// Down while swaying side to side along a sine wave
+    Weave { amplitude: f32, period: f32 },
+    /// Down while swaying side to side in straight lines
+    Zigzag { amplitude: f32, period: f32 },
+    /// Down for `delay`, then at `speed` towards where the player was at that
+    /// moment
+    Dive { delay: f32, speed: f32 },
+    /// Down to height `y`, sideways by `distance`, then down again
+    Strafe { y: f32, distance: f32 },
+    /// Down to height `y`, wait there for `duration`, then back up and away
+    Hover { y: f32, duration: f32 },
+    /// Down to height `y`, then side to side along a sine wave for good
+    Patrol { y: f32, amplitude: f32, period: f32 },
+    /// Through `points`, relative to the spawn position, on a smooth curve,
+    /// then straight down
+    Path { points: Vec<Vec2> },
+}
+
+impl EnemyMovement {
+    /// The same movement with left and right swapped
+    pub fn mirrored(&self) -> Self {
+        match self.clone() {
+            EnemyMovement::Weave { amplitude, period } => EnemyMovement::Weave {
+                amplitude: -amplitude,
+                period,
+            },
+            EnemyMovement::Zigzag { amplitude, period } => EnemyMovement::Zigzag {
+                amplitude: -amplitude,
+                period,
+            },
+            EnemyMovement::Strafe { y, distance } => EnemyMovement::Strafe {
+                y,
+                distance: -distance,
+            },
+            EnemyMovement::Patrol {
+                y,
+                amplitude,
+                period,
+            } => EnemyMovement::Patrol {
+                y,
+                amplitude: -amplitude,
+                period,
+            },
+            EnemyMovement::Path { points } => EnemyMovement::Path {
+                points: points
+                    .into_iter()
+                    .map(|point| Vec2::new(-point.x, point.y))
+                    .collect(),
+            },
+            movement => movement,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let positive = |name: &str, value: f32| {
+            if value.is_finite() && value > 0.0 {
+                Ok(())
+            } else {
+                Err(format!("movement {name} must be above zero, not {value}"))
+            }
+        };
+        let finite = |name: &str, value: f32| {
+            if value.is_finite() {
+                Ok(())
+            } else {
+                Err(format!("movement {name} must be a number, not {value}"))
+            }
+        };
+
+        match self {
+            EnemyMovement::Descend => Ok(()),
+            EnemyMovement::Weave { amplitude, period }
+            | EnemyMovement::Zigzag { amplitude, period } => {
+                finite("amplitude", *amplitude)?;
+                positive("period", *period)
+            }
+            EnemyMovement::Dive { delay, speed } => {
+                finite("delay", *delay)?;
+                positive("speed", *speed)
+            }
+            EnemyMovement::Strafe { y, distance } => {
+                finite("y", *y)?;
+                finite("distance", *distance)
+            }
+            EnemyMovement::Hover { y, duration } => {
+                finite("y", *y)?;
+                finite("duration", *duration)
+            }
+            EnemyMovement::Patrol {
+                y,
+                amplitude,
+                period,
+            } => {
+                finite("y", *y)?;
+                finite("amplitude", *amplitude)?;
+                positive("period", *period)
+            }
+            EnemyMovement::Path { points } => {
+                if points.is_empty() {
+                    return Err("movement Path needs at least one point".into());
+                }
+                match points.iter().find(|point| !point.is_finite()) {
+                    Some(point) => Err(format!("movement path point {point} is not a number")),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+/// How far an enemy is along its [`EnemyMovement`]
+#[derive(Component, Debug)]
+pub struct MovementProgress {
+    origin: Vec2,
+    elapsed: f32,
+    /// When the current stage of the movement started, e.g. when a hovering
+    /// enemy arrived
+    stage_started: Option<f32>,
+    /// Direction locked in by a dive
+    heading: Option<Vec2>,
+    /// Segment of a path and how far along it, from 0 to 1
+    path_position: (usize, f32),
+    /// Where in its sway a patrol picked up, in radians
+    sway_start: f32,
+    /// Flying back up and out of the play area
+    pub retreating: bool,
+}
+
+impl MovementProgress {
+    pub fn new(origin: Vec2) -> Self {
+        Self {
+            origin,
+            elapsed: 0.0,
+            stage_started: None,
+            heading: None,
+            path_position: (0, 0.0),
+            sway_start: 0.0,
+            retreating: false,
+        }
+    }
+
+    /// Start a new movement from the current position, keeping the origin it
+    /// sways and strafes around
+    pub fn restart(&mut self) {
+        *self = Self::new(self.origin);
+    }
+}
+
+pub fn apply_enemy_movement(
+    mut query: Populated<
+        (
+            &EnemyMovement,
+            &MovementSpeed,
+            &mut MovementProgress,
+            &mut Transform,
+        ),
+        With<Enemy>,
+    >,
+    player: Query<&Transform, (With<Player>, Without<Enemy>)>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_secs();
+    let player = player
+        .get_single()
+        .ok()
+        .map(|player| player.translation.truncate());
+
+    for (movement, speed, mut progress, mut transform) in query.iter_mut() {
+        progress.elapsed += delta;
+        let position = transform.translation.truncate();
+        let descended = Vec2::new(position.x, position.y - speed.0 * delta);
+
+        let next = match movement {
+            EnemyMovement::Descend => descended,
+            EnemyMovement::Weave { amplitude, period } => {
+                let sway = (TAU * progress.elapsed / period).sin();
+                Vec2::new(progress.origin.x + amplitude * sway, descended.y)
+            }
+            EnemyMovement::Zigzag { amplitude, period } => {
+                let sway = triangle_wave(progress.elapsed / period);
+                Vec2::new(progress.origin.x + amplitude * sway, descended.y)
+            }
+            EnemyMovement::Dive {
+                delay,
+                speed: dive_speed,
+            } => {
+                if progress.elapsed < *delay {
+                    descended
+                } else {
+                    let heading = *progress.heading.get_or_insert_with(|| {
+                        player
+                            .and_then(|player| (player - position).try_normalize())
+                            .unwrap_or(Vec2::NEG_Y)
+                    });
+                    position + heading * dive_speed * delta
+                }
+            }
+            EnemyMovement::Strafe { y, distance } => {
+                let target_x = progress.origin.x + distance;
+                if position.y > *y {
+                    Vec2::new(position.x, descended.y.max(*y))
+                } else if position.x != target_x {
+                    let step = speed.0 * delta;
+                    Vec2::new(
+                        position.x + (target_x - position.x).clamp(-step, step),
+                        position.y,
+                    )
+                } else {
+                    descended
+                }
+            }
+            EnemyMovement::Hover { y, duration } => {
+                if progress.retreating || position.y > *y {
+                    let step = speed.0 * delta;
+                    match progress.retreating {
+                        true => Vec2::new(position.x, position.y + step),
+                        false => Vec2::new(position.x, (position.y - step).max(*y)),
+                    }
+                } else {
+                    let elapsed = progress.elapsed;
+                    let arrived = *progress.stage_started.get_or_insert(elapsed);
+                    if elapsed - arrived >= *duration {
+                        progress.retreating = true;
+                    }
+                    position
+                }
+            }
+            EnemyMovement::Patrol {
+                y,
+                amplitude,
+                period,
+            } => {
+                if position.y > *y {
+                    Vec2::new(position.x, descended.y.max(*y))
+                } else {
+                    let elapsed = progress.elapsed;
+                    let arrived = match progress.stage_started {
+                        Some(arrived) => arrived,
+                        None => {
+                            // Pick up the sway wherever the enemy already is,
+                            // so a restarted patrol doesn't jump
+                            let offset = (position.x - progress.origin.x) / amplitude;
+                            progress.sway_start = if offset.is_finite() {
+                                offset.clamp(-1.0, 1.0).asin()
+                            } else {
+                                0.0
+                            };
+                            *progress.stage_started.insert(elapsed)
+                        }
+                    };
+                    let sway = (TAU * (elapsed - arrived) / period + progress.sway_start).sin();
+                    Vec2::new(progress.origin.x + amplitude * sway, position.y)
+                }
+            }
+            EnemyMovement::Path { points } => {
+                follow_path(&mut progress, points, speed.0 * delta).unwrap_or(descended)
+            }
+        };
+
+        transform.translation = next.extend(transform.translation.z);
+    }
+}
+
+/// Move `distance` further along a Catmull-Rom spline through the spawn
+/// position and `points`, or `None` once past the last point
+fn follow_path(progress: &mut MovementProgress, points: &[Vec2], distance: f32) -> Option<Vec2> {
+    let origin = progress.origin;
+    let point = |index: usize| match index {
+        0 => origin,
+        _ => origin + points[(index - 1).min(points.len() - 1)],
+    };
+
+    let (mut segment, mut along) = progress.path_position;
+    let mut remaining = distance;
+    while segment < points.len() {
+        let length = point(segment).distance(point(segment + 1));
+        if length > 0.0 {
+            let step = remaining / length;
+            if along + step < 1.0 {
+                along += step;
+                break;
+            }
+            remaining -= (1.0 - along) * length;
+        }
+        segment += 1;
+        along = 0.0;
+    }
+    progress.path_position = (segment, along);
+
+    if segment >= points.len() {
+        return None;
+    }
+    Some(catmull_rom(
+        point(segment.saturating_sub(1)),
+        point(segment),
+        point(segment + 1),
+        point(segment + 2),
+        along,
+    ))
+}
+
+fn catmull_rom(p0: Vec2, p1: Vec2, p2: Vec2, p3: Vec2, t: f32) -> Vec2 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * (2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+/// Like a sine wave, but in straight lines: 0 at 0, 1 at 0.25, -1 at 0.75
+fn triangle_wave(phase: f32) -> f32 {
+    let phase = phase.rem_euclid(1.0);
+    if phase < 0.25 {
+        4.0 * phase
+    } else if phase < 0.75 {
+        2.0 - 4.0 * phase
+    } else {
+        4.0 * phase - 4.0
+    }
+}
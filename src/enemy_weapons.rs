@@ -0,0 +1,223 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    components::Bounds,
+    enemies::Enemy,
+    enemy_defs::AnimationFrames,
+    game::{despawn_all, GameplaySet, StartRun, StartRunSet},
+    game_state::GameState,
+    interpolation::InterpolatedTranslation,
+    play_area::PlayArea,
+    player::Player,
+    sprite_animation::{update_animations, AnimationConfig},
+    sprite_assets::SpriteAssets,
+    AppState,
+};
+
+/// Frames in `laser-bolts.png`, which projectiles are drawn from
+const PROJECTILE_FRAMES: usize = 4;
+/// How far past the edge of the play area a projectile is removed
+const PROJECTILE_MARGIN: f32 = 32.0;
+const PROJECTILE_SCALE: f32 = 2.0;
+
+/// Enemies shooting at the player, with the `weapon` from their file in
+/// `assets/enemies`. Hits are handled by the `CollisionsPlugin`.
+pub struct EnemyWeaponsPlugin;
+impl Plugin for EnemyWeaponsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            StartRun,
+            despawn_all::<EnemyProjectile>.in_set(StartRunSet::Despawn),
+        )
+        .add_systems(
+            FixedUpdate,
+            (
+                fire_enemy_weapons.in_set(GameplaySet::Spawn),
+                (apply_projectile_movement, remove_out_of_bounds_projectiles)
+                    .chain()
+                    .in_set(GameplaySet::Move),
+            )
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            Update,
+            update_animations::<EnemyProjectile>.run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// What an enemy fires, as written in its file
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct WeaponDef {
+    /// Shots per second
+    pub fire_rate: f32,
+    /// Frames of `laser-bolts.png` the projectile is animated with
+    pub projectile: AnimationFrames,
+    pub projectile_speed: f32,
+    pub damage: i32,
+    pub aim: Aim,
+}
+
+impl WeaponDef {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.fire_rate.is_finite() || self.fire_rate <= 0.0 {
+            return Err(format!(
+                "fire_rate must be above zero, not {}",
+                self.fire_rate
+            ));
+        }
+        if !self.projectile_speed.is_finite() || self.projectile_speed <= 0.0 {
+            return Err(format!(
+                "projectile_speed must be above zero, not {}",
+                self.projectile_speed
+            ));
+        }
+        if self.damage <= 0 {
+            return Err(format!("damage must be above zero, not {}", self.damage));
+        }
+        let projectile = self.projectile;
+        if projectile.first > projectile.last || projectile.last >= PROJECTILE_FRAMES {
+            return Err(format!(
+                "projectile frames must be in order and below {PROJECTILE_FRAMES}, not {} to {}",
+                projectile.first, projectile.last
+            ));
+        }
+        if projectile.fps == 0 {
+            return Err("projectile.fps must not be zero".into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Which way a shot goes
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub enum Aim {
+    /// Straight down
+    Down,
+    /// At where the player is when the shot is fired
+    AtPlayer,
+    /// `count` projectiles fanned out evenly over `angle` degrees, centered
+    /// on straight down
+    Spread { count: u32, angle: f32 },
+}
+
+impl Aim {
+    /// Direction of every projectile of a shot, given the direction to the
+    /// player
+    fn directions(&self, to_player: Vec2) -> Vec<Vec2> {
+        match *self {
+            Aim::Down => vec![Vec2::NEG_Y],
+            Aim::AtPlayer => vec![to_player],
+            Aim::Spread { count, angle } => (0..count)
+                .map(|index| {
+                    let offset = match count {
+                        1 => 0.0,
+                        _ => index as f32 / (count - 1) as f32 - 0.5,
+                    };
+                    Vec2::from_angle((offset * angle).to_radians()).rotate(Vec2::NEG_Y)
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct EnemyWeapon {
+    def: WeaponDef,
+    timer: Timer,
+}
+
+impl EnemyWeapon {
+    /// The first shot comes one cooldown after the enemy spawns
+    pub fn new(def: WeaponDef) -> Self {
+        Self {
+            def,
+            timer: Timer::from_seconds(1.0 / def.fire_rate, TimerMode::Repeating),
+        }
+    }
+
+    /// Shoot `factor` times as often
+    pub fn scale_fire_rate(&mut self, factor: f32) {
+        let cooldown = self.timer.duration().div_f32(factor);
+        self.timer.set_duration(cooldown);
+    }
+}
+
+#[derive(Component)]
+pub struct EnemyProjectile {
+    pub velocity: Vec2,
+    pub damage: i32,
+}
+
+fn fire_enemy_weapons(
+    mut commands: Commands,
+    sprite_assets: Res<SpriteAssets>,
+    play_area: Res<PlayArea>,
+    time: Res<Time>,
+    player: Option<Single<&Transform, With<Player>>>,
+    mut query: Query<(&mut EnemyWeapon, &Transform, &Bounds), With<Enemy>>,
+) {
+    for (mut weapon, transform, bounds) in &mut query {
+        weapon.timer.tick(time.delta());
+        // Enemies only shoot once they are on screen
+        if !weapon.timer.just_finished() || transform.translation.y > play_area.half_size().y {
+            continue;
+        }
+
+        let muzzle = transform.translation - Vec3::Y * bounds.size.y / 2.0;
+        let to_player = player
+            .as_ref()
+            .map(|player| (player.translation - muzzle).truncate())
+            .and_then(|offset| offset.try_normalize())
+            .unwrap_or(Vec2::NEG_Y);
+
+        let WeaponDef {
+            projectile,
+            projectile_speed,
+            damage,
+            aim,
+            ..
+        } = weapon.def;
+        for direction in aim.directions(to_player) {
+            commands.spawn((
+                EnemyProjectile {
+                    velocity: direction * projectile_speed,
+                    damage,
+                },
+                StateScoped(AppState::Game),
+                sprite_assets
+                    .bullet
+                    .sprite(projectile.first, PROJECTILE_SCALE),
+                AnimationConfig::new(projectile.first, projectile.last, projectile.fps),
+                Transform::from_translation(muzzle),
+                InterpolatedTranslation::new(muzzle),
+            ));
+        }
+    }
+}
+
+fn apply_projectile_movement(
+    mut query: Populated<(&EnemyProjectile, &mut Transform)>,
+    time: Res<Time>,
+) {
+    for (projectile, mut transform) in query.iter_mut() {
+        transform.translation += (projectile.velocity * time.delta_secs()).extend(0.0);
+    }
+}
+
+fn remove_out_of_bounds_projectiles(
+    mut commands: Commands,
+    query: Query<(Entity, &Transform), With<EnemyProjectile>>,
+    play_area: Res<PlayArea>,
+) {
+    let limit = play_area.half_size() + Vec2::splat(PROJECTILE_MARGIN);
+    for (entity, transform) in &query {
+        let position = transform.translation.truncate();
+        if position.x.abs() > limit.x || position.y.abs() > limit.y {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -1,19 +1,31 @@
 use bevy::{audio::Volume, prelude::*};
 
 use crate::{
-    audio::GameSounds,
+    game::{clear_events, despawn_all, StartRun, StartRunSet},
     game_state::GameState,
+    loading::SoundAssets,
     settings::Settings,
     sprite_animation::{AnimationConfig, SPRITE_FPS},
+    sprite_assets::SpriteAssets,
+    AppState,
 };
 
 pub struct ExplosionPlugin;
 impl Plugin for ExplosionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<DestroyedEvent>().add_systems(
-            Update,
-            (handle_destroy_event, update_explosion_animation).run_if(in_state(GameState::Playing)),
-        );
+        app.add_event::<DestroyedEvent>()
+            .add_systems(
+                StartRun,
+                (
+                    despawn_all::<Explosion>.in_set(StartRunSet::Despawn),
+                    clear_events::<DestroyedEvent>.in_set(StartRunSet::Reset),
+                ),
+            )
+            .add_systems(
+                Update,
+                (handle_destroy_event, update_explosion_animation)
+                    .run_if(in_state(GameState::Playing)),
+            );
     }
 }
 
@@ -29,37 +41,28 @@ pub struct DestroyedEvent(pub DestroyedData);
 
 fn handle_destroy_event(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
-    game_sounds: Res<GameSounds>,
+    sprite_assets: Res<SpriteAssets>,
+    sounds: Option<Res<SoundAssets>>,
     mut destroyed_event: EventReader<DestroyedEvent>,
     settings: Res<Settings>,
 ) {
-    let explosion_image = asset_server.load("../assets/explosion.png");
-
-    let explosion_atlas = TextureAtlasLayout::from_grid(UVec2::splat(16), 5, 1, None, None);
-    let explosion_atlas_handle = texture_atlases.add(explosion_atlas);
-
     for event in destroyed_event.read() {
-        commands.spawn((
+        let mut explosion = commands.spawn((
             Explosion,
-            AudioPlayer::new(game_sounds.explosion.clone()),
-            PlaybackSettings {
-                volume: Volume::new(settings.effect_volume),
-                ..default()
-            },
+            StateScoped(AppState::Game),
             Transform::from_translation(event.0.position),
-            Sprite {
-                image: explosion_image.clone(),
-                texture_atlas: Some(TextureAtlas {
-                    layout: explosion_atlas_handle.clone(),
-                    index: 0,
-                }),
-                custom_size: Some(Vec2::splat(16.0) * 2.0),
-                ..default()
-            },
+            sprite_assets.explosion.sprite(0, 2.0),
             AnimationConfig::new(0, 4, SPRITE_FPS),
         ));
+        if let Some(sounds) = &sounds {
+            explosion.insert((
+                AudioPlayer::new(sounds.explosion.clone()),
+                PlaybackSettings {
+                    volume: Volume::new(settings.effect_volume),
+                    ..default()
+                },
+            ));
+        }
     }
 }
 
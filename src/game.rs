@@ -1,30 +1,66 @@
-use bevy::prelude::*;
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
 
+use crate::balance::BalancePlugin;
 use crate::collisions::CollisionsPlugin;
 use crate::enemies::EnemiesPlugin;
 use crate::explosion::ExplosionPlugin;
+use crate::input::PlayerInputPlugin;
+use crate::interpolation::InterpolationPlugin;
+use crate::levels::LevelsPlugin;
+use crate::play_area::PlayAreaPlugin;
 use crate::player::PlayerPlugin;
 use crate::powerups::PowerupsPlugin;
-use crate::scoreboard::ScoreboardPlugin;
+use crate::rng::RngPlugin;
+use crate::sprite_assets::SpriteAssetsPlugin;
 use crate::AppState;
-//use crate::systems::despawn_screen;
 
+/// Gameplay simulation. Movement, shooting, spawning and collisions run in the
+/// fixed timestep, which runs at 64 Hz by default.
 pub struct GamePlugin;
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<GameRestartEvent>()
-            .add_systems(OnEnter(AppState::Game), game_setup)
+            .init_resource::<Score>()
+            .init_schedule(StartRun)
+            .configure_sets(
+                StartRun,
+                (StartRunSet::Despawn, StartRunSet::Reset, StartRunSet::Spawn).chain(),
+            )
+            .configure_sets(
+                FixedUpdate,
+                (
+                    GameplaySet::Input,
+                    GameplaySet::Spawn,
+                    GameplaySet::Move,
+                    GameplaySet::Collide,
+                    GameplaySet::Resolve,
+                )
+                    .chain(),
+            )
+            .configure_sets(
+                FixedUpdate,
+                GameplaySet::Move.ambiguous_with(GameplaySet::Move),
+            )
+            .add_systems(OnEnter(AppState::Game), start_run)
+            .add_systems(PreUpdate, start_run.run_if(on_event::<GameRestartEvent>))
+            .add_systems(StartRun, reset_score.in_set(StartRunSet::Reset))
+            .add_systems(OnExit(AppState::Game), reset_score)
             .add_systems(Update, game.run_if(in_state(AppState::Game)))
             .add_plugins((
-                ScoreboardPlugin,
+                BalancePlugin,
+                SpriteAssetsPlugin,
+                PlayAreaPlugin,
+                PlayerInputPlugin,
+                InterpolationPlugin,
+                RngPlugin,
                 PlayerPlugin,
                 EnemiesPlugin,
+                LevelsPlugin,
                 CollisionsPlugin,
                 PowerupsPlugin,
                 ExplosionPlugin,
             ));
         //.add_systems(OnExit(GameState::Playing), despawn_screen::<GameScreen>);
-        //TODO: handle exiting to the game to main menu
     }
 }
 
@@ -34,6 +70,65 @@ impl Plugin for GamePlugin {
 #[derive(Event, Default)]
 pub struct GameRestartEvent;
 
-fn game_setup() {}
+/// Sets up a run, both when a game is entered and when it is restarted, so a
+/// restarted run starts from exactly the same state as the first one.
+///
+/// Gameplay plugins add their set-up to one of the [`StartRunSet`]s.
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct StartRun;
+
+/// Steps of [`StartRun`], run in order
+#[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum StartRunSet {
+    /// Remove whatever the previous run left behind
+    Despawn,
+    /// Reset scores, counters, timers, random streams and pending events
+    Reset,
+    /// Spawn what a run starts with
+    Spawn,
+}
+
+/// Steps of a gameplay tick in `FixedUpdate`, run in order so that a tick
+/// plays out the same way every time, e.g. for replays.
+///
+/// Gameplay plugins add their fixed timestep systems to one of these.
+#[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum GameplaySet {
+    /// Turn the player's input into what the ship should do
+    Input,
+    /// Spawn bullets, enemies, projectiles and powerups
+    Spawn,
+    /// Move everything, and remove what left the play area. Each plugin only
+    /// moves its own kind of entity, so they can move in any order.
+    Move,
+    /// Check what hit what
+    Collide,
+    /// Apply damage, destroy enemies, and update health, buffs and bosses
+    Resolve,
+}
+
+fn start_run(world: &mut World) {
+    world.run_schedule(StartRun);
+}
+
+/// Despawn every entity with a `T` component, along with its children
+pub fn despawn_all<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Drop events of type `T` that haven't been handled yet
+pub fn clear_events<T: Event>(mut events: ResMut<Events<T>>) {
+    events.clear();
+}
+
+// This resource tracks the game's score
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct Score(usize);
+
+fn reset_score(mut score: ResMut<Score>) {
+    **score = 0;
+}
 
 fn game() {}
@@ -1,4 +1,7 @@
-use crate::{game_state::GameState, theme::Palette};
+use crate::{
+    game_state::GameState, input::Action, loading::UiAssets, rng::RunSeed, settings::Settings,
+    theme::Palette,
+};
 use bevy::prelude::*;
 
 const GAME_OVER_FONT_SIZE: f32 = 80.0;
@@ -10,17 +13,22 @@ struct GameOverText;
 
 impl Plugin for GameOverPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::GameOver), setup_game_over)
-            .add_systems(OnExit(GameState::GameOver), cleanup_game_over);
+        app.add_systems(OnEnter(GameState::GameOver), setup_game_over);
     }
 }
 
-fn setup_game_over(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let font = asset_server.load("../assets/atari_games.ttf");
+fn setup_game_over(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    seed: Res<RunSeed>,
+    settings: Res<Settings>,
+) {
+    let font = ui_assets.font.clone();
 
     commands
         .spawn((
             GameOverText,
+            StateScoped(GameState::GameOver),
             Node {
                 height: Val::Percent(100.0),
                 width: Val::Percent(100.0),
@@ -42,7 +50,27 @@ fn setup_game_over(mut commands: Commands, asset_server: Res<AssetServer>) {
             ));
 
             parent.spawn((
-                Text::new("Press `R` to restart"),
+                Text::new(format!("Seed: {}", seed.0)),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Palette::TEXT_PAUSED),
+                Node {
+                    margin: UiRect {
+                        top: Val::Px(20.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ));
+
+            parent.spawn((
+                Text::new(format!(
+                    "Press {} to restart",
+                    settings.bindings.describe(Action::Restart)
+                )),
                 TextFont {
                     font: font.clone(),
                     font_size: 24.0,
@@ -57,11 +85,25 @@ fn setup_game_over(mut commands: Commands, asset_server: Res<AssetServer>) {
                     ..default()
                 },
             ));
-        });
-}
 
-fn cleanup_game_over(mut commands: Commands, query: Query<Entity, With<GameOverText>>) {
-    for entity in query.iter() {
-        commands.entity(entity).despawn_recursive();
-    }
+            parent.spawn((
+                Text::new(format!(
+                    "Press {} for the main menu",
+                    settings.bindings.describe(Action::MainMenu)
+                )),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Palette::TEXT_PRIMARY),
+                Node {
+                    margin: UiRect {
+                        top: Val::Px(20.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ));
+        });
 }
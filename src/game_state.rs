@@ -1,16 +1,25 @@
 use bevy::prelude::*;
 
+use crate::AppState;
+
 pub struct GameStatePlugin;
 impl Plugin for GameStatePlugin {
     fn build(&self, app: &mut App) {
-        app.init_state::<GameState>();
+        app.add_sub_state::<GameState>()
+            .enable_state_scoped_entities::<AppState>()
+            .enable_state_scoped_entities::<GameState>();
     }
 }
 
-#[derive(States, Clone, Copy, Default, Eq, PartialEq, Debug, Hash)]
+/// State of the game in progress. It only exists in `AppState::Game` and starts
+/// out as `Playing` every time a game is entered.
+#[derive(SubStates, Clone, Copy, Default, Eq, PartialEq, Debug, Hash)]
+#[source(AppState = AppState::Game)]
 pub enum GameState {
     #[default]
     Playing,
     Paused,
+    /// Between two stages
+    LevelComplete,
     GameOver,
 }
@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use bevy::{
+    core::FrameCount, input::InputPlugin, prelude::*, render::texture::ImagePlugin,
+    state::app::StatesPlugin, time::TimeUpdateStrategy,
+};
+
+use crate::play_area::PlayArea;
+
+/// Runs the gameplay simulation without a window, GPU or audio device.
+///
+/// Time advances by a fixed `frame_time` every update, so frames can be
+/// stepped as fast as the machine allows. It defaults to the fixed timestep,
+/// making every update run exactly one gameplay tick.
+pub struct HeadlessPlugin {
+    pub play_area: PlayArea,
+    pub frame_time: Duration,
+    /// Exit the app after this many frames
+    pub frame_limit: Option<u32>,
+}
+
+impl Default for HeadlessPlugin {
+    fn default() -> Self {
+        Self {
+            play_area: PlayArea::default(),
+            frame_time: Time::<Fixed>::default().timestep(),
+            frame_limit: None,
+        }
+    }
+}
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            MinimalPlugins,
+            StatesPlugin,
+            InputPlugin,
+            AssetPlugin::default(),
+            ImagePlugin::default_nearest(),
+        ))
+        .init_asset::<TextureAtlasLayout>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(self.frame_time))
+        .insert_resource(self.play_area);
+
+        if let Some(frame_limit) = self.frame_limit {
+            app.insert_resource(FrameLimit(frame_limit))
+                .add_systems(PostUpdate, exit_after_frame_limit);
+        }
+    }
+}
+
+#[derive(Resource)]
+struct FrameLimit(u32);
+
+fn exit_after_frame_limit(
+    frame_count: Res<FrameCount>,
+    frame_limit: Res<FrameLimit>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    if frame_count.0 >= frame_limit.0 {
+        app_exit_events.send(AppExit::Success);
+    }
+}
@@ -1,17 +1,41 @@
 use bevy::prelude::*;
 
 use crate::{
-    components::{Health, PlayerStats},
+    bosses::Boss,
+    buffs::{ActiveBuffs, BuffKind},
+    collisions::PlayerHurtEvent,
+    components::{Health, MaxHealth, PlayerStats, WeaponLevel},
+    game::{despawn_all, StartRun, StartRunSet},
+    levels::{Levels, StageProgress},
+    loading::UiAssets,
     player::Player,
     theme::Palette,
     AppState,
 };
 
+/// Seconds the health readout stays red after the player is hurt
+const DAMAGE_FLASH_TIME: f32 = 0.5;
+
 pub struct HudPlugin;
 impl Plugin for HudPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(AppState::Game), (cleanup_hud, setup_hud).chain())
-            .add_systems(Update, (update_hud).run_if(in_state(AppState::Game)));
+        app.add_systems(
+            StartRun,
+            (
+                despawn_all::<HudUi>.in_set(StartRunSet::Despawn),
+                setup_hud.in_set(StartRunSet::Spawn),
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                update_hud,
+                update_stage,
+                update_boss_health_bar,
+                flash_health_on_damage,
+            )
+                .run_if(in_state(AppState::Game)),
+        );
     }
 }
 
@@ -21,23 +45,82 @@ struct HudUi;
 #[derive(Component)]
 struct HudHealth;
 
+#[derive(Component)]
+struct HudHealthValue;
+
 #[derive(Component)]
 struct HudFireRate;
 
 #[derive(Component)]
 struct HudSpeed;
 
-fn cleanup_hud(mut commands: Commands, query: Query<Entity, With<HudUi>>) {
-    for entity in query.iter() {
-        commands.entity(entity).despawn_recursive();
-    }
-}
+#[derive(Component)]
+struct HudWeapon;
+
+#[derive(Component)]
+struct HudStage;
+
+/// Shown while a boss is fighting
+#[derive(Component)]
+pub struct BossHealthBar;
+
+#[derive(Component)]
+pub struct BossHealthFill;
+
+fn setup_hud(mut commands: Commands, ui_assets: Res<UiAssets>) {
+    let text_font = ui_assets.font.clone();
 
-fn setup_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let text_font = asset_server.load("../assets/atari_games.ttf");
     commands
         .spawn((
             HudUi,
+            BossHealthBar,
+            StateScoped(AppState::Game),
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(48.0),
+                left: Val::Percent(25.0),
+                width: Val::Percent(50.0),
+                height: Val::Px(16.0),
+                border: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            BorderColor(Palette::TEXT_PRIMARY),
+            Visibility::Hidden,
+        ))
+        .with_child((
+            BossHealthFill,
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            BackgroundColor(Palette::TEXT_GAME_OVER),
+        ));
+
+    // Stage and wave, across from the scoreboard
+    commands.spawn((
+        HudUi,
+        HudStage,
+        StateScoped(AppState::Game),
+        Text::default(),
+        TextFont {
+            font: text_font.clone(),
+            font_size: 28.0,
+            ..default()
+        },
+        TextColor(Palette::TEXT_PRIMARY),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(5.0),
+            left: Val::Px(16.0),
+            ..default()
+        },
+    ));
+
+    commands
+        .spawn((
+            HudUi,
+            StateScoped(AppState::Game),
             Node {
                 width: Val::Percent(100.0),
                 height: Val::Percent(10.0),
@@ -100,6 +183,25 @@ fn setup_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
                         },
                         TextColor(Palette::TEXT_PRIMARY),
                     ));
+                    p.spawn((
+                        HudWeapon,
+                        Text::new("Weapon: "),
+                        TextFont {
+                            font: text_font.clone(),
+                            font_size: 28.0,
+                            ..default()
+                        },
+                        TextColor(Palette::TEXT_PRIMARY),
+                    ))
+                    .with_child((
+                        TextSpan::default(),
+                        TextFont {
+                            font: text_font.clone(),
+                            font_size: 28.0,
+                            ..default()
+                        },
+                        TextColor(Palette::TEXT_PRIMARY),
+                    ));
                 });
 
             parent
@@ -124,6 +226,7 @@ fn setup_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
                         TextColor(Palette::TEXT_PRIMARY),
                     ))
                     .with_child((
+                        HudHealthValue,
                         TextSpan::default(),
                         TextFont {
                             font: text_font.clone(),
@@ -140,11 +243,75 @@ fn update_hud(
     health_root: Single<Entity, (With<HudHealth>, With<Text>)>,
     fire_rate_root: Single<Entity, (With<HudFireRate>, With<Text>)>,
     speed_root: Single<Entity, (With<HudSpeed>, With<Text>)>,
-    player_health: Single<&Health, With<Player>>,
-    player_stats: Single<&PlayerStats, With<Player>>,
+    weapon_root: Single<Entity, (With<HudWeapon>, With<Text>)>,
+    player: Single<
+        (
+            &Health,
+            &MaxHealth,
+            &PlayerStats,
+            &ActiveBuffs,
+            &WeaponLevel,
+        ),
+        With<Player>,
+    >,
     mut writer: TextUiWriter,
 ) {
-    *writer.text(*health_root, 1) = player_health.0.to_string();
-    *writer.text(*fire_rate_root, 1) = player_stats.fire_rate.to_string();
-    *writer.text(*speed_root, 1) = player_stats.speed.to_string();
+    let (player_health, max_health, player_stats, buffs, weapon_level) = *player;
+    *writer.text(*health_root, 1) = format!("{}/{}", player_health.0, max_health.0);
+    *writer.text(*fire_rate_root, 1) =
+        buff_text(player_stats.fire_rate, buffs.remaining(BuffKind::FireRate));
+    *writer.text(*speed_root, 1) = buff_text(player_stats.speed, buffs.remaining(BuffKind::Speed));
+    *writer.text(*weapon_root, 1) = format!("{}/{}", weapon_level.0, WeaponLevel::MAX);
+}
+
+fn flash_health_on_damage(
+    time: Res<Time>,
+    mut hurt_events: EventReader<PlayerHurtEvent>,
+    mut flash: Local<Timer>,
+    mut color: Single<&mut TextColor, With<HudHealthValue>>,
+) {
+    if hurt_events.read().count() > 0 {
+        *flash = Timer::from_seconds(DAMAGE_FLASH_TIME, TimerMode::Once);
+    }
+    flash.tick(time.delta());
+    color.0 = match flash.finished() {
+        true => Palette::TEXT_PRIMARY,
+        false => Palette::TEXT_GAME_OVER,
+    };
+}
+
+/// A boosted stat with the seconds left on its buff, rounded up
+fn buff_text(value: f32, remaining: Option<f32>) -> String {
+    match remaining {
+        Some(remaining) => format!("{value:.2} ({}s)", remaining.ceil()),
+        None => value.to_string(),
+    }
+}
+
+fn update_stage(
+    progress: Res<StageProgress>,
+    levels: Res<Levels>,
+    mut text: Single<&mut Text, With<HudStage>>,
+) {
+    let waves = levels.stage(progress.stage).waves.len();
+    text.0 = format!(
+        "Stage {} - Wave {}/{waves}",
+        progress.stage + 1,
+        (progress.wave + 1).min(waves)
+    );
+}
+
+fn update_boss_health_bar(
+    bosses: Query<(&Health, &Boss)>,
+    mut bar: Single<&mut Visibility, With<BossHealthBar>>,
+    mut fill: Single<&mut Node, With<BossHealthFill>>,
+) {
+    // A score boss can come in during a wave's boss fight, so show the one
+    // closest to going down
+    let Some((health, boss)) = bosses.iter().min_by_key(|(health, _)| health.0) else {
+        **bar = Visibility::Hidden;
+        return;
+    };
+    **bar = Visibility::Inherited;
+    fill.width = Val::Percent(100.0 * health.0.max(0) as f32 / boss.max_health.max(1) as f32);
 }
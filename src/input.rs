@@ -0,0 +1,317 @@
+use std::collections::BTreeMap;
+
+use bevy::{
+    input::{
+        gamepad::{GamepadConnection, GamepadConnectionEvent},
+        InputSystem,
+    },
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{game_state::GameState, settings::Settings};
+
+/// Player input sampled once per frame.
+///
+/// Gameplay runs in the fixed timestep, which can tick zero or several times
+/// per frame, so it reads the latest sample from here instead of polling the
+/// keyboard itself.
+#[derive(Resource, Default, Clone, Copy, Debug, PartialEq)]
+pub struct PlayerInput {
+    pub direction: Vec2,
+    pub shoot: bool,
+}
+
+/// Something the player can do. Keys are bound to actions in
+/// [`InputBindings`] and gamepad buttons in [`Action::gamepad_buttons`], and
+/// everything past this module reads the actions from `ButtonInput<Action>`
+/// instead of reading keys or buttons.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Fire,
+    Pause,
+    Restart,
+    MainMenu,
+    Quit,
+}
+
+impl Action {
+    pub const ALL: [Action; 9] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Fire,
+        Action::Pause,
+        Action::Restart,
+        Action::MainMenu,
+        Action::Quit,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::MoveUp => "Move up",
+            Action::MoveDown => "Move down",
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::Fire => "Fire",
+            Action::Pause => "Pause",
+            Action::Restart => "Restart",
+            Action::MainMenu => "Main menu",
+            Action::Quit => "Quit",
+        }
+    }
+
+    fn default_keys(&self) -> Vec<KeyCode> {
+        match self {
+            Action::MoveUp => vec![KeyCode::ArrowUp, KeyCode::KeyW],
+            Action::MoveDown => vec![KeyCode::ArrowDown, KeyCode::KeyS],
+            Action::MoveLeft => vec![KeyCode::ArrowLeft, KeyCode::KeyA],
+            Action::MoveRight => vec![KeyCode::ArrowRight, KeyCode::KeyD],
+            Action::Fire => vec![KeyCode::Space],
+            Action::Pause => vec![KeyCode::Escape],
+            Action::Restart => vec![KeyCode::KeyR],
+            Action::MainMenu => vec![KeyCode::KeyM],
+            Action::Quit => vec![KeyCode::KeyQ],
+        }
+    }
+
+    /// The gamepad buttons that trigger the action. Unlike keys they can't be
+    /// rebound.
+    pub fn gamepad_buttons(&self) -> &'static [GamepadButton] {
+        match self {
+            Action::MoveUp => &[GamepadButton::DPadUp],
+            Action::MoveDown => &[GamepadButton::DPadDown],
+            Action::MoveLeft => &[GamepadButton::DPadLeft],
+            Action::MoveRight => &[GamepadButton::DPadRight],
+            Action::Fire => &[
+                GamepadButton::South,
+                GamepadButton::West,
+                GamepadButton::RightTrigger,
+                GamepadButton::RightTrigger2,
+            ],
+            Action::Pause => &[GamepadButton::Start, GamepadButton::East],
+            Action::Restart => &[GamepadButton::North],
+            Action::MainMenu => &[GamepadButton::Select],
+            Action::Quit => &[],
+        }
+    }
+}
+
+/// The keys bound to each action, saved with the rest of the [`Settings`]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(transparent)]
+pub struct InputBindings(BTreeMap<Action, Vec<KeyCode>>);
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        Self(
+            Action::ALL
+                .into_iter()
+                .map(|action| (action, action.default_keys()))
+                .collect(),
+        )
+    }
+}
+
+impl InputBindings {
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.0.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// The action other than `action` that `key` is bound to
+    pub fn conflict(&self, action: Action, key: KeyCode) -> Option<Action> {
+        self.0
+            .iter()
+            .find(|(other, keys)| **other != action && keys.contains(&key))
+            .map(|(other, _)| *other)
+    }
+
+    /// Bind `key` to `action`, in place of the binding at `slot` or after the
+    /// others when there is none
+    pub fn bind(&mut self, action: Action, slot: Option<usize>, key: KeyCode) {
+        let keys = self.0.entry(action).or_default();
+        if keys.contains(&key) {
+            return;
+        }
+        match slot.and_then(|slot| keys.get_mut(slot)) {
+            Some(bound) => *bound = key,
+            None => keys.push(key),
+        }
+    }
+
+    pub fn unbind(&mut self, action: Action, slot: usize) {
+        if let Some(keys) = self.0.get_mut(&action) {
+            if slot < keys.len() {
+                keys.remove(slot);
+            }
+        }
+    }
+
+    /// Give actions added since the bindings were saved their default keys
+    pub fn add_missing_actions(&mut self) {
+        for action in Action::ALL {
+            self.0
+                .entry(action)
+                .or_insert_with(|| action.default_keys());
+        }
+    }
+
+    /// The keys of `action` for showing to the player, e.g. "`Esc` or `P`"
+    pub fn describe(&self, action: Action) -> String {
+        match self.keys(action) {
+            [] => "(unbound)".to_string(),
+            keys => keys
+                .iter()
+                .map(|key| format!("`{}`", key_name(*key)))
+                .collect::<Vec<_>>()
+                .join(" or "),
+        }
+    }
+}
+
+/// Short name of a key, e.g. "W" for `KeyCode::KeyW`
+pub fn key_name(key: KeyCode) -> String {
+    match key {
+        KeyCode::Escape => "Esc".to_string(),
+        KeyCode::ArrowUp => "Up".to_string(),
+        KeyCode::ArrowDown => "Down".to_string(),
+        KeyCode::ArrowLeft => "Left".to_string(),
+        KeyCode::ArrowRight => "Right".to_string(),
+        _ => {
+            let name = format!("{key:?}");
+            ["Key", "Digit"]
+                .iter()
+                .find_map(|prefix| name.strip_prefix(prefix))
+                .map_or_else(|| name.clone(), str::to_string)
+        }
+    }
+}
+
+/// Keys go to whatever inserted this instead of to the actions, e.g. the
+/// controls page waiting for a new binding. Actions stay released until it
+/// is removed, including the ones on a gamepad.
+#[derive(Resource)]
+pub struct CapturingKeys;
+
+/// Updates `ButtonInput<Action>` and [`PlayerInput`] in `PreUpdate`
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ActionSystem;
+
+pub struct PlayerInputPlugin;
+impl Plugin for PlayerInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerInput>()
+            .init_resource::<ButtonInput<Action>>()
+            .add_systems(
+                PreUpdate,
+                (
+                    update_actions,
+                    sample_player_input,
+                    pause_on_gamepad_disconnect,
+                )
+                    .chain()
+                    .in_set(ActionSystem)
+                    .after(InputSystem),
+            );
+    }
+}
+
+fn update_actions(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    settings: Res<Settings>,
+    capturing: Option<Res<CapturingKeys>>,
+    mut actions: ResMut<ButtonInput<Action>>,
+) {
+    actions.clear();
+
+    for action in Action::ALL {
+        let pressed = capturing.is_none()
+            && (keyboard.any_pressed(settings.bindings.keys(action).iter().copied())
+                || gamepads
+                    .iter()
+                    .any(|gamepad| gamepad.any_pressed(action.gamepad_buttons().iter().copied())));
+        if pressed {
+            actions.press(action);
+        } else {
+            actions.release(action);
+        }
+    }
+}
+
+/// A left stick pushed past the deadzone steers with its analog position,
+/// otherwise the movement actions steer at full speed
+fn sample_player_input(
+    actions: Res<ButtonInput<Action>>,
+    gamepads: Query<&Gamepad>,
+    settings: Res<Settings>,
+    mut input: ResMut<PlayerInput>,
+) {
+    let stick = gamepads
+        .iter()
+        .map(|gamepad| apply_deadzone(gamepad.left_stick(), settings.gamepad_deadzone))
+        .find(|stick| *stick != Vec2::ZERO);
+
+    input.direction = stick.unwrap_or_else(|| get_input_direction(&actions));
+    input.shoot = actions.pressed(Action::Fire);
+}
+
+/// Ignore a stick inside the `deadzone` radius and scale the rest back to
+/// 0..=1, so movement starts slow at the edge of the deadzone
+fn apply_deadzone(stick: Vec2, deadzone: f32) -> Vec2 {
+    let length = stick.length();
+    if length <= deadzone {
+        return Vec2::ZERO;
+    }
+    let scaled = ((length - deadzone) / (1.0 - deadzone)).min(1.0);
+    stick / length * scaled
+}
+
+/// Losing the gamepad mid-game would leave the player flying blind, so pause
+fn pause_on_gamepad_disconnect(
+    mut connection_events: EventReader<GamepadConnectionEvent>,
+    game_state: Option<Res<State<GameState>>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    for event in connection_events.read() {
+        if event.connection != GamepadConnection::Disconnected {
+            continue;
+        }
+        if game_state
+            .as_ref()
+            .is_some_and(|state| *state.get() == GameState::Playing)
+        {
+            info!("Pausing the game, its gamepad was disconnected");
+            next_game_state.set(GameState::Paused);
+        }
+    }
+}
+
+fn get_input_direction(actions: &ButtonInput<Action>) -> Vec2 {
+    let mut direction = Vec2::ZERO;
+
+    if actions.pressed(Action::MoveLeft) {
+        direction.x -= 1.0;
+    }
+    if actions.pressed(Action::MoveRight) {
+        direction.x += 1.0;
+    }
+    if actions.pressed(Action::MoveUp) {
+        direction.y += 1.0;
+    }
+    if actions.pressed(Action::MoveDown) {
+        direction.y -= 1.0;
+    }
+
+    // Normalize the direction to prevent faster diagonal movement
+    if direction != Vec2::ZERO {
+        direction = direction.normalize();
+    }
+
+    direction
+}
@@ -0,0 +1,56 @@
+use bevy::{app::RunFixedMainLoopSystem, prelude::*};
+
+/// Smooths the rendered position of an entity moved in the fixed timestep.
+///
+/// `Transform` holds the simulated position while fixed steps run. Once they
+/// are done for the frame it is blended between the last two steps, and the
+/// simulated position is put back before the next step.
+#[derive(Component, Debug)]
+pub struct InterpolatedTranslation {
+    previous: Vec3,
+    current: Vec3,
+}
+
+impl InterpolatedTranslation {
+    pub fn new(translation: Vec3) -> Self {
+        Self {
+            previous: translation,
+            current: translation,
+        }
+    }
+}
+
+pub struct InterpolationPlugin;
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(FixedFirst, restore_simulated_translation)
+            .add_systems(FixedLast, store_simulated_translation)
+            .add_systems(
+                RunFixedMainLoop,
+                interpolate_translation.in_set(RunFixedMainLoopSystem::AfterFixedMainLoop),
+            );
+    }
+}
+
+fn restore_simulated_translation(mut query: Query<(&mut Transform, &mut InterpolatedTranslation)>) {
+    for (mut transform, mut interpolated) in &mut query {
+        transform.translation = interpolated.current;
+        interpolated.previous = interpolated.current;
+    }
+}
+
+fn store_simulated_translation(mut query: Query<(&Transform, &mut InterpolatedTranslation)>) {
+    for (transform, mut interpolated) in &mut query {
+        interpolated.current = transform.translation;
+    }
+}
+
+fn interpolate_translation(
+    fixed_time: Res<Time<Fixed>>,
+    mut query: Query<(&mut Transform, &InterpolatedTranslation)>,
+) {
+    let overstep = fixed_time.overstep_fraction();
+    for (mut transform, interpolated) in &mut query {
+        transform.translation = interpolated.previous.lerp(interpolated.current, overstep);
+    }
+}
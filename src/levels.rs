@@ -0,0 +1,588 @@
+use std::fmt;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    ecs::system::SystemParam,
+    prelude::*,
+};
+use serde::Deserialize;
+
+use crate::{
+    bosses::Boss,
+    components::{Health, MovementSpeed},
+    enemies::{enemy_spawning_enabled, spawn_enemies, spawn_enemy, Enemy, EnemyCount},
+    enemy_defs::{enemy_def_path, EnemyDef, EnemyDefs, EnemyType},
+    enemy_movement::EnemyMovement,
+    enemy_weapons::EnemyWeapon,
+    game::{GameplaySet, Score, StartRun, StartRunSet},
+    game_state::GameState,
+    loading::AssetManifest,
+    play_area::PlayArea,
+    AppState,
+};
+
+/// Relative to the `assets` folder
+const LEVELS_PATH: &str = "campaign.levels.ron";
+const LEVELS_EXTENSION: &str = "levels.ron";
+
+/// Runs the stages from `assets/campaign.levels.ron`: every wave of a stage in
+/// turn, then a stage clear break before the next one. Once the last stage is
+/// cleared the stages start over, with the enemies getting tougher each time.
+pub struct LevelsPlugin;
+impl Plugin for LevelsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Levels>()
+            .register_asset_loader(LevelsLoader)
+            .init_resource::<LevelsFile>()
+            .init_resource::<StageProgress>()
+            .add_systems(OnEnter(AppState::Loading), add_levels_to_manifest)
+            .add_systems(OnExit(AppState::Loading), insert_levels)
+            .add_systems(StartRun, reset_stage_progress.in_set(StartRunSet::Reset))
+            .add_systems(
+                FixedUpdate,
+                (
+                    (spawn_score_bosses, run_waves)
+                        .chain()
+                        .run_if(enemy_spawning_enabled)
+                        .before(spawn_enemies),
+                    scale_new_enemies.after(spawn_enemies),
+                )
+                    .in_set(GameplaySet::Spawn)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                FixedUpdate,
+                finish_stage_clear
+                    .before(spawn_score_bosses)
+                    .in_set(GameplaySet::Spawn)
+                    .run_if(in_state(GameState::LevelComplete)),
+            );
+    }
+}
+
+/// The stages of a run, as written in `assets/campaign.levels.ron`
+#[derive(Asset, Resource, TypePath, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Levels {
+    /// Seconds between clearing a stage and starting the next one
+    pub stage_clear_time: f32,
+    /// Added to the enemies' multipliers for every stage after the first
+    pub difficulty_step: Difficulty,
+    pub stages: Vec<Stage>,
+    /// Bosses that come in when the score gets high enough, whatever the wave
+    #[serde(default)]
+    pub score_bosses: Vec<ScoreBoss>,
+    /// Every enemy the waves name, so a misspelled one fails to load
+    #[serde(skip)]
+    #[dependency]
+    enemies: Vec<Handle<EnemyDef>>,
+}
+
+impl Levels {
+    /// The stage at `index`, counting on from the first stage again past the
+    /// last one
+    pub fn stage(&self, index: usize) -> &Stage {
+        &self.stages[index % self.stages.len()]
+    }
+
+    /// How much tougher enemies are in the stage at `index`
+    pub fn difficulty(&self, index: usize) -> Difficulty {
+        let step = self.difficulty_step;
+        let stages = index as f32;
+        Difficulty {
+            speed: 1.0 + step.speed * stages,
+            health: 1.0 + step.health * stages,
+            fire_rate: 1.0 + step.fire_rate * stages,
+        }
+    }
+
+    fn validate(&self) -> Result<(), LevelsError> {
+        let invalid = |message: String| Err(LevelsError::Invalid(message));
+
+        if !self.stage_clear_time.is_finite() || self.stage_clear_time < 0.0 {
+            return invalid(format!(
+                "stage_clear_time must not be negative, not {}",
+                self.stage_clear_time
+            ));
+        }
+        let step = self.difficulty_step;
+        if let Some(value) = [step.speed, step.health, step.fire_rate]
+            .into_iter()
+            .find(|value| !value.is_finite() || *value < 0.0)
+        {
+            return invalid(format!("difficulty_step must not be negative, not {value}"));
+        }
+        if self
+            .score_bosses
+            .windows(2)
+            .any(|pair| pair[0].score > pair[1].score)
+        {
+            return invalid("score_bosses must be in order of score".into());
+        }
+        if self.stages.is_empty() {
+            return invalid("there must be at least one stage".into());
+        }
+
+        for stage in &self.stages {
+            if stage.waves.is_empty() {
+                return invalid(format!("stage {} has no waves", stage.name));
+            }
+            for (index, wave) in stage.waves.iter().enumerate() {
+                let wave_name = format!("wave {} of stage {}", index + 1, stage.name);
+                if !wave.delay.is_finite() || wave.delay < 0.0 {
+                    return invalid(format!("{wave_name} has a negative delay, {}", wave.delay));
+                }
+                if wave.spawns.is_empty() {
+                    return invalid(format!("{wave_name} spawns nothing"));
+                }
+                if wave
+                    .spawns
+                    .windows(2)
+                    .any(|pair| pair[0].at() > pair[1].at())
+                {
+                    return invalid(format!("{wave_name} must list its spawns in order of `at`"));
+                }
+                for spawn in &wave.spawns {
+                    spawn.validate().map_err(|message| {
+                        LevelsError::Invalid(format!("{wave_name}: {message}"))
+                    })?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A boss, named after its file in `assets/enemies`, that comes in once per
+/// run when the score reaches `score`
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ScoreBoss {
+    pub score: usize,
+    pub enemy: String,
+}
+
+/// Multipliers for the speed, health and fire rate of enemies
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Difficulty {
+    pub speed: f32,
+    pub health: f32,
+    pub fire_rate: f32,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Stage {
+    pub name: String,
+    pub waves: Vec<Wave>,
+}
+
+/// Enemies that come in together. The next wave starts once all of them have
+/// spawned and none are left.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Wave {
+    /// Seconds to wait before the wave starts
+    pub delay: f32,
+    pub spawns: Vec<Spawn>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub enum Spawn {
+    /// `count` enemies named after their file in `assets/enemies`, lined up in
+    /// a `formation` centered on `x` just above the top of the play area.
+    /// They move the way their file says unless the group has a `movement`.
+    Group {
+        at: f32,
+        enemy: String,
+        count: u32,
+        x: f32,
+        formation: Formation,
+        #[serde(default)]
+        movement: Option<EnemyMovement>,
+    },
+    /// The random spawner, as tuned in `assets/gameplay.balance.ron`, for
+    /// `duration` seconds
+    Random { at: f32, duration: f32 },
+}
+
+impl Spawn {
+    /// Seconds into the wave, after its delay, at which this spawn starts
+    pub fn at(&self) -> f32 {
+        match self {
+            Spawn::Group { at, .. } | Spawn::Random { at, .. } => *at,
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let at = self.at();
+        if !at.is_finite() || at < 0.0 {
+            return Err(format!("`at` must not be negative, not {at}"));
+        }
+        match self {
+            Spawn::Group {
+                count,
+                formation,
+                movement,
+                ..
+            } => {
+                if *count == 0 {
+                    return Err("a group must have at least one enemy".into());
+                }
+                let spacing = formation.spacing();
+                if !spacing.is_finite() || spacing < 0.0 {
+                    return Err(format!(
+                        "formation spacing must not be negative, not {spacing}"
+                    ));
+                }
+                match movement {
+                    Some(movement) => movement.validate(),
+                    None => Ok(()),
+                }
+            }
+            Spawn::Random { duration, .. } => {
+                if !duration.is_finite() || *duration <= 0.0 {
+                    return Err(format!("duration must be above zero, not {duration}"));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// How a group is lined up, `spacing` world units apart
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub enum Formation {
+    /// Side by side, coming in together
+    Row { spacing: f32 },
+    /// One behind the other, coming in one at a time
+    Column { spacing: f32 },
+    /// A V pointing down, led by the first enemy
+    Vee { spacing: f32 },
+}
+
+impl Formation {
+    fn spacing(&self) -> f32 {
+        match self {
+            Formation::Row { spacing }
+            | Formation::Column { spacing }
+            | Formation::Vee { spacing } => *spacing,
+        }
+    }
+
+    /// Offset of the enemy at `index` from the front of the formation, with
+    /// the ones further back higher up
+    fn offset(&self, index: u32, count: u32) -> Vec2 {
+        let index = index as f32;
+        match *self {
+            Formation::Row { spacing } => {
+                Vec2::new((index - (count - 1) as f32 / 2.0) * spacing, 0.0)
+            }
+            Formation::Column { spacing } => Vec2::new(0.0, index * spacing),
+            Formation::Vee { spacing } => {
+                let rank = ((index + 1.0) / 2.0).floor();
+                let side = if index as u32 % 2 == 1 { -1.0 } else { 1.0 };
+                Vec2::new(side * rank * spacing, rank * spacing)
+            }
+        }
+    }
+}
+
+/// How far the run has come through the stages
+#[derive(Resource, Default, Debug)]
+pub struct StageProgress {
+    /// Counting from 0, and on past the last stage once they start over
+    pub stage: usize,
+    /// Counting from 0. Equal to the number of waves once the stage is clear.
+    pub wave: usize,
+    /// Seconds since the current wave started waiting for its delay
+    wave_time: f32,
+    /// Spawns of the current wave that have happened
+    spawned: usize,
+    /// Wave time until which the random spawner runs
+    random_until: f32,
+    /// Seconds since the stage was cleared
+    clear_time: f32,
+    /// Score bosses that have come in
+    score_bosses: usize,
+}
+
+impl StageProgress {
+    /// Whether the current wave has the random spawner running
+    pub fn random_spawns(&self) -> bool {
+        self.wave_time < self.random_until
+    }
+}
+
+/// Whether the random spawner should run, for use with `run_if`. It holds off
+/// while a boss is fighting.
+pub fn random_spawns_active(progress: Res<StageProgress>, bosses: Query<(), With<Boss>>) -> bool {
+    progress.random_spawns() && bosses.is_empty()
+}
+
+#[derive(Resource)]
+struct LevelsFile(Handle<Levels>);
+
+impl FromWorld for LevelsFile {
+    fn from_world(world: &mut World) -> Self {
+        Self(world.resource::<AssetServer>().load(LEVELS_PATH))
+    }
+}
+
+fn add_levels_to_manifest(mut manifest: ResMut<AssetManifest>, file: Res<LevelsFile>) {
+    manifest.add(file.0.clone());
+}
+
+fn insert_levels(mut commands: Commands, file: Res<LevelsFile>, assets: Res<Assets<Levels>>) {
+    if let Some(levels) = assets.get(&file.0) {
+        commands.insert_resource(levels.clone());
+    }
+}
+
+fn reset_stage_progress(mut progress: ResMut<StageProgress>) {
+    *progress = StageProgress::default();
+}
+
+/// What the levels need to spawn enemies
+#[derive(SystemParam)]
+struct LevelSpawner<'w, 's> {
+    commands: Commands<'w, 's>,
+    enemy_defs: Res<'w, EnemyDefs>,
+    enemy_count: ResMut<'w, EnemyCount>,
+    play_area: Res<'w, PlayArea>,
+}
+
+impl LevelSpawner<'_, '_> {
+    /// Where an enemy of `enemy_type` at `x` comes in, just above the play area
+    fn entry(&self, enemy_type: EnemyType, x: f32) -> Vec2 {
+        let def = self.enemy_defs.get(enemy_type);
+        Vec2::new(x, self.play_area.half_size().y + def.size().y / 2.0)
+    }
+
+    fn spawn(&mut self, enemy_type: EnemyType, position: Vec2, movement: EnemyMovement) {
+        spawn_enemy(
+            &mut self.commands,
+            &self.enemy_defs,
+            &mut self.enemy_count,
+            enemy_type,
+            position.extend(1.0),
+            movement,
+        );
+    }
+}
+
+/// Bring in the next score boss once the score reaches it
+fn spawn_score_bosses(
+    mut spawner: LevelSpawner,
+    mut progress: ResMut<StageProgress>,
+    levels: Res<Levels>,
+    score: Res<Score>,
+) {
+    let Some(boss) = levels.score_bosses.get(progress.score_bosses) else {
+        return;
+    };
+    if **score < boss.score {
+        return;
+    }
+    progress.score_bosses += 1;
+
+    let Some(enemy_type) = spawner.enemy_defs.find(&boss.enemy) else {
+        return;
+    };
+    let movement = spawner.enemy_defs.get(enemy_type).movement.clone();
+    let position = spawner.entry(enemy_type, 0.0);
+    spawner.spawn(enemy_type, position, movement);
+}
+
+/// Spawn the current wave as its time comes, and move on to the next wave or
+/// the stage clear break once it is over. Waves hold off while a boss is
+/// fighting.
+fn run_waves(
+    mut spawner: LevelSpawner,
+    mut progress: ResMut<StageProgress>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    levels: Res<Levels>,
+    time: Res<Time>,
+    bosses: Query<(), With<Boss>>,
+) {
+    let stage = levels.stage(progress.stage);
+    // The stage is clear and the break is about to start
+    let Some(wave) = stage.waves.get(progress.wave) else {
+        return;
+    };
+    if !bosses.is_empty() {
+        return;
+    }
+
+    progress.wave_time += time.delta_secs();
+    let wave_time = progress.wave_time - wave.delay;
+
+    while let Some(spawn) = wave.spawns.get(progress.spawned) {
+        if spawn.at() > wave_time {
+            break;
+        }
+        progress.spawned += 1;
+
+        match spawn {
+            Spawn::Group {
+                enemy,
+                count,
+                x,
+                formation,
+                movement,
+                ..
+            } => {
+                let Some(enemy_type) = spawner.enemy_defs.find(enemy) else {
+                    continue;
+                };
+                let movement = movement
+                    .clone()
+                    .unwrap_or_else(|| spawner.enemy_defs.get(enemy_type).movement.clone());
+                let front = spawner.entry(enemy_type, *x);
+                for index in 0..*count {
+                    let position = front + formation.offset(index, *count);
+                    spawner.spawn(enemy_type, position, movement.clone());
+                }
+            }
+            Spawn::Random { at, duration } => {
+                progress.random_until = wave.delay + at + duration;
+            }
+        }
+    }
+
+    let wave_over = progress.spawned == wave.spawns.len()
+        && !progress.random_spawns()
+        && spawner.enemy_count.total() == 0;
+    if !wave_over {
+        return;
+    }
+
+    progress.wave += 1;
+    progress.wave_time = 0.0;
+    progress.spawned = 0;
+    progress.random_until = 0.0;
+    if progress.wave == stage.waves.len() {
+        info!("Stage {} clear", progress.stage + 1);
+        progress.clear_time = 0.0;
+        next_game_state.set(GameState::LevelComplete);
+    }
+}
+
+/// Make enemies as tough as the current stage, whether a wave or the random
+/// spawner spawned them
+fn scale_new_enemies(
+    progress: Res<StageProgress>,
+    levels: Res<Levels>,
+    mut query: Query<(&mut MovementSpeed, &mut Health, Option<&mut EnemyWeapon>), Added<Enemy>>,
+) {
+    let difficulty = levels.difficulty(progress.stage);
+    if difficulty == levels.difficulty(0) {
+        return;
+    }
+
+    for (mut speed, mut health, weapon) in &mut query {
+        speed.0 *= difficulty.speed;
+        health.0 = ((health.0 as f32 * difficulty.health).round() as i32).max(1);
+        if let Some(mut weapon) = weapon {
+            weapon.scale_fire_rate(difficulty.fire_rate);
+        }
+    }
+}
+
+/// Start the next stage once the break is over
+fn finish_stage_clear(
+    mut progress: ResMut<StageProgress>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    levels: Res<Levels>,
+    time: Res<Time>,
+) {
+    progress.clear_time += time.delta_secs();
+    if progress.clear_time >= levels.stage_clear_time {
+        progress.stage += 1;
+        progress.wave = 0;
+        info!(
+            "Stage {}: {}",
+            progress.stage + 1,
+            levels.stage(progress.stage).name
+        );
+        next_game_state.set(GameState::Playing);
+    }
+}
+
+#[derive(Debug)]
+pub enum LevelsError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Invalid(String),
+}
+
+impl fmt::Display for LevelsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelsError::Io(error) => write!(f, "could not read the file: {error}"),
+            LevelsError::Parse(error) => write!(f, "{error}"),
+            LevelsError::Invalid(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for LevelsError {}
+
+impl From<std::io::Error> for LevelsError {
+    fn from(error: std::io::Error) -> Self {
+        LevelsError::Io(error)
+    }
+}
+
+impl From<ron::error::SpannedError> for LevelsError {
+    fn from(error: ron::error::SpannedError) -> Self {
+        LevelsError::Parse(error)
+    }
+}
+
+struct LevelsLoader;
+
+impl AssetLoader for LevelsLoader {
+    type Asset = Levels;
+    type Settings = ();
+    type Error = LevelsError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Levels, LevelsError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let mut levels: Levels = ron::de::from_bytes(&bytes)?;
+        levels.validate()?;
+
+        let mut names: Vec<&str> = levels
+            .stages
+            .iter()
+            .flat_map(|stage| &stage.waves)
+            .flat_map(|wave| &wave.spawns)
+            .filter_map(|spawn| match spawn {
+                Spawn::Group { enemy, .. } => Some(enemy.as_str()),
+                Spawn::Random { .. } => None,
+            })
+            .chain(levels.score_bosses.iter().map(|boss| boss.enemy.as_str()))
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        let enemies = names
+            .into_iter()
+            .map(|name| load_context.load(enemy_def_path(name)))
+            .collect();
+        levels.enemies = enemies;
+
+        Ok(levels)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[LEVELS_EXTENSION]
+    }
+}
@@ -0,0 +1,271 @@
+use bevy::{
+    asset::{LoadState, RecursiveDependencyLoadState},
+    prelude::*,
+};
+
+use crate::{theme::Palette, AppState};
+
+/// Paths are relative to the `assets` folder
+const FONT_PATH: &str = "atari_games.ttf";
+const WINDOW_BACKGROUND_PATH: &str = "window_background.png";
+const MUSIC_PATH: &str = "8bit-spaceshooter.ogg";
+const SHOOT_SOUND_PATH: &str = "laser.ogg";
+const EXPLOSION_SOUND_PATH: &str = "explosion.ogg";
+pub const BACKGROUND_SHADER_PATH: &str = "background_shader.frag";
+
+const PROGRESS_BAR_WIDTH: f32 = 400.0;
+
+/// Holds the game in [`AppState::Loading`] until everything in the
+/// [`AssetManifest`] has loaded, then moves on to `next_state`. A path that
+/// fails to load is logged with its error, and keeps the game from starting.
+pub struct LoadingPlugin {
+    pub next_state: AppState,
+    /// Exit instead of waiting on the loading screen, for when there is no
+    /// screen to show the error on
+    pub exit_on_failure: bool,
+}
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AssetManifest>()
+            .init_resource::<LoadingProgress>()
+            .insert_resource(LoadingNextState(self.next_state))
+            .insert_resource(ExitOnFailure(self.exit_on_failure))
+            .add_systems(Update, track_loading.run_if(in_state(AppState::Loading)));
+    }
+}
+
+/// Loads the fonts, UI images, sounds and shaders a windowed game needs, and
+/// shows a progress bar while [`LoadingPlugin`] waits for them
+pub struct LoadingScreenPlugin;
+
+impl Plugin for LoadingScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<UiAssets>()
+            .init_resource::<SoundAssets>()
+            .init_resource::<ShaderAssets>()
+            .add_systems(
+                OnEnter(AppState::Loading),
+                (add_screen_assets_to_manifest, setup_loading_screen),
+            )
+            .add_systems(
+                Update,
+                update_loading_screen
+                    .run_if(in_state(AppState::Loading).and(resource_changed::<LoadingProgress>)),
+            );
+    }
+}
+
+/// Every asset the game needs before it can start. Plugins add their
+/// handles when [`AppState::Loading`] is entered.
+#[derive(Resource, Default)]
+pub struct AssetManifest(Vec<UntypedHandle>);
+
+impl AssetManifest {
+    pub fn add(&mut self, handle: impl Into<UntypedHandle>) {
+        self.0.push(handle.into());
+    }
+}
+
+/// How far [`AppState::Loading`] has come
+#[derive(Resource, Default)]
+pub struct LoadingProgress {
+    pub loaded: usize,
+    pub total: usize,
+    /// The first asset that failed to load, and why
+    pub error: Option<String>,
+}
+
+/// Fonts and images used by menus and overlays
+#[derive(Resource)]
+pub struct UiAssets {
+    pub font: Handle<Font>,
+    pub window_background: Handle<Image>,
+}
+
+impl FromWorld for UiAssets {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        Self {
+            font: asset_server.load(FONT_PATH),
+            window_background: asset_server.load(WINDOW_BACKGROUND_PATH),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct SoundAssets {
+    pub music: Handle<AudioSource>,
+    pub shoot: Handle<AudioSource>,
+    pub explosion: Handle<AudioSource>,
+}
+
+impl FromWorld for SoundAssets {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        Self {
+            music: asset_server.load(MUSIC_PATH),
+            shoot: asset_server.load(SHOOT_SOUND_PATH),
+            explosion: asset_server.load(EXPLOSION_SOUND_PATH),
+        }
+    }
+}
+
+/// Materials name their shaders by path, so these handles only keep the
+/// shaders loaded from the start
+#[derive(Resource)]
+pub struct ShaderAssets {
+    pub background: Handle<Shader>,
+}
+
+impl FromWorld for ShaderAssets {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        Self {
+            background: asset_server.load(BACKGROUND_SHADER_PATH),
+        }
+    }
+}
+
+#[derive(Resource)]
+struct LoadingNextState(AppState);
+
+#[derive(Resource)]
+struct ExitOnFailure(bool);
+
+#[derive(Component)]
+struct LoadingProgressBar;
+
+#[derive(Component)]
+struct LoadingText;
+
+fn add_screen_assets_to_manifest(
+    mut manifest: ResMut<AssetManifest>,
+    ui: Res<UiAssets>,
+    sounds: Res<SoundAssets>,
+    shaders: Res<ShaderAssets>,
+) {
+    manifest.add(ui.font.clone());
+    manifest.add(ui.window_background.clone());
+    manifest.add(sounds.music.clone());
+    manifest.add(sounds.shoot.clone());
+    manifest.add(sounds.explosion.clone());
+    manifest.add(shaders.background.clone());
+}
+
+fn setup_loading_screen(mut commands: Commands) {
+    // The game's font is still loading, so this screen uses the default one
+    commands
+        .spawn((
+            StateScoped(AppState::Loading),
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Px(PROGRESS_BAR_WIDTH),
+                        height: Val::Px(16.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    BorderColor(Palette::TEXT_PRIMARY),
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        LoadingProgressBar,
+                        Node {
+                            width: Val::Percent(0.0),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        BackgroundColor(Palette::TEXT_PRIMARY),
+                    ));
+                });
+
+            parent.spawn((
+                LoadingText,
+                Text::new("Loading"),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Palette::TEXT_PAUSED),
+                Node {
+                    margin: UiRect {
+                        top: Val::Px(12.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn track_loading(
+    asset_server: Res<AssetServer>,
+    manifest: Res<AssetManifest>,
+    next_state: Res<LoadingNextState>,
+    exit_on_failure: Res<ExitOnFailure>,
+    mut progress: ResMut<LoadingProgress>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    if progress.error.is_some() {
+        return;
+    }
+
+    let mut loaded = 0;
+    for handle in &manifest.0 {
+        let Some((load_state, _, dependencies_load_state)) = asset_server.get_load_states(handle)
+        else {
+            continue;
+        };
+        let error = match (load_state, dependencies_load_state) {
+            (LoadState::Failed(error), _) | (_, RecursiveDependencyLoadState::Failed(error)) => {
+                error
+            }
+            (LoadState::Loaded, RecursiveDependencyLoadState::Loaded) => {
+                loaded += 1;
+                continue;
+            }
+            _ => continue,
+        };
+
+        let path = handle
+            .path()
+            .map_or_else(|| "an asset".to_string(), |path| path.to_string());
+        error!("Could not load {path}: {error}");
+        progress.error = Some(format!("Could not load {path}\n{error}"));
+        if exit_on_failure.0 {
+            app_exit_events.send(AppExit::error());
+        }
+        return;
+    }
+
+    progress.loaded = loaded;
+    progress.total = manifest.0.len();
+    if loaded == manifest.0.len() {
+        next_app_state.set(next_state.0);
+    }
+}
+
+fn update_loading_screen(
+    progress: Res<LoadingProgress>,
+    mut progress_bar: Single<&mut Node, With<LoadingProgressBar>>,
+    mut text: Single<(&mut Text, &mut TextColor), With<LoadingText>>,
+) {
+    if let Some(error) = &progress.error {
+        text.0 .0 = error.clone();
+        text.1 .0 = Palette::TEXT_GAME_OVER;
+    } else if progress.total > 0 {
+        progress_bar.width = Val::Percent(100.0 * progress.loaded as f32 / progress.total as f32);
+    }
+}
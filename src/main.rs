@@ -1,32 +1,59 @@
 use background::BackgroundPlugin;
-use bevy::prelude::*;
+use bevy::{log::LogPlugin, prelude::*};
 //use bevy_dev_tools::fps_overlay::FpsOverlayPlugin;
 use bevy_rand::prelude::*;
 use game::{GamePlugin, GameRestartEvent};
 use game_state::{GameState, GameStatePlugin};
+use headless::HeadlessPlugin;
 use hud::hud::HudPlugin;
+use input::Action;
+use loading::{LoadingPlugin, LoadingScreenPlugin};
 use menu::menu::MenuPlugin;
-use settings::Settings;
+use play_area::PlayAreaCamera;
+use pointer::PointerInputPlugin;
+use replay::{Replay, ReplayPlugin};
+use scoreboard::ScoreboardPlugin;
+use settings::{PersistSettingsPlugin, Settings};
 
 mod audio;
 mod background;
+mod balance;
+mod bosses;
+mod buffs;
 mod collisions;
 mod components;
 mod enemies;
+mod enemy_defs;
+mod enemy_movement;
+mod enemy_weapons;
 mod explosion;
 mod game;
 mod game_over;
 mod game_state;
+mod headless;
 mod hud;
+mod input;
+mod interpolation;
+mod levels;
+mod loading;
 mod menu;
 mod paused;
+mod play_area;
 mod player;
+mod pointer;
 mod powerups;
+mod replay;
+mod rng;
 mod scoreboard;
 mod settings;
 mod sprite_animation;
+mod sprite_assets;
+mod stage_clear;
 //mod stepping;
-mod systems;
+#[cfg(test)]
+mod test_support;
+#[cfg(test)]
+mod tests;
 mod theme;
 
 use audio::GameAudioPlugin;
@@ -35,67 +62,158 @@ const BACKGROUND_COLOR: Color = Color::srgb(0.0, 0.0, 0.0); // Changed to black
 
 #[derive(States, Clone, Copy, Default, Eq, PartialEq, Debug, Hash)]
 pub enum AppState {
+    Loading,
     #[default]
     Menu,
     Game,
 }
 
-fn main() {
-    // NOTE: Common resolution that most monitors scale well with is 640x360px
-    // let resolution = Vec2::new(640., 360.) * 2.;
-    App::new()
-        .add_plugins((DefaultPlugins, EntropyPlugin::<WyRand>::default()))
-        //.add_plugins(FpsOverlayPlugin::default())
-        .insert_resource(ClearColor(BACKGROUND_COLOR))
-        .insert_resource(Settings::new())
-        .init_state::<AppState>()
-        .add_plugins(GameAudioPlugin)
-        .add_plugins((
-            GameStatePlugin,
-            MenuPlugin,
-            HudPlugin,
-            GamePlugin,
-            BackgroundPlugin,
-            game_over::GameOverPlugin,
-            paused::PausedPlugin,
+fn main() -> AppExit {
+    let mut app = App::new();
+
+    let replay = arg_value("--replay").map(|path| {
+        Replay::load(&path).unwrap_or_else(|error| {
+            eprintln!("Could not play back {path}: {error}");
+            std::process::exit(1);
+        })
+    });
+
+    let headless = has_arg("--headless");
+    if headless {
+        // Run only the gameplay simulation, starting straight into a game
+        app.add_plugins((
+            HeadlessPlugin {
+                frame_limit: arg_value("--frames").and_then(|frames| frames.parse().ok()),
+                ..default()
+            },
+            LogPlugin::default(),
+            LoadingPlugin {
+                next_state: AppState::Game,
+                exit_on_failure: true,
+            },
         ))
-        // .add_plugins(
-        //     stepping::SteppingPlugin::default()
-        //         .add_schedule(Update)
-        //         .add_schedule(FixedUpdate)
-        //         .at(Val::Percent(35.0), Val::Percent(50.0)),
-        // )
-        .add_systems(Startup, setup)
-        // Add our gameplay simulation systems to the fixed timestep schedule
-        // which runs at 64 Hz by default
+        .insert_state(AppState::Loading);
+    } else {
+        app.add_plugins(DefaultPlugins)
+            //.add_plugins(FpsOverlayPlugin::default())
+            .insert_resource(ClearColor(BACKGROUND_COLOR))
+            .insert_state(AppState::Loading)
+            .add_plugins((
+                LoadingPlugin {
+                    // Replays skip the menu and start playing straight away
+                    next_state: match replay {
+                        Some(_) => AppState::Game,
+                        None => AppState::Menu,
+                    },
+                    exit_on_failure: false,
+                },
+                LoadingScreenPlugin,
+            ))
+            .add_plugins((GameAudioPlugin, PersistSettingsPlugin))
+            .add_plugins((
+                MenuPlugin,
+                HudPlugin,
+                ScoreboardPlugin,
+                BackgroundPlugin,
+                game_over::GameOverPlugin,
+                paused::PausedPlugin,
+                stage_clear::StageClearPlugin,
+                PointerInputPlugin,
+            ))
+            // .add_plugins(
+            //     stepping::SteppingPlugin::default()
+            //         .add_schedule(Update)
+            //         .add_schedule(FixedUpdate)
+            //         .at(Val::Percent(35.0), Val::Percent(50.0)),
+            // )
+            .add_systems(Startup, setup);
+    }
+
+    // Headless runs are for checking the simulation, so they leave the
+    // player's settings alone
+    let mut settings = if headless {
+        Settings::new()
+    } else {
+        Settings::load()
+    };
+    if let Some(seed) = arg_value("--seed") {
+        settings.seed = Some(seed.parse().expect("--seed must be a whole number"));
+    }
+
+    let replay_plugin = match replay {
+        Some(replay) => {
+            settings.seed = Some(replay.header.seed);
+            ReplayPlugin::Playback {
+                replay,
+                exit_when_done: headless,
+            }
+        }
+        None => ReplayPlugin::Record {
+            directory: "replays".into(),
+        },
+    };
+
+    app.add_plugins(EntropyPlugin::<WyRand>::default())
+        .insert_resource(settings)
+        .add_plugins((GameStatePlugin, GamePlugin))
+        .add_plugins(replay_plugin)
         .add_systems(Update, handle_exit)
-        .run();
+        .run()
+}
+
+fn has_arg(name: &str) -> bool {
+    std::env::args().any(|arg| arg == name)
+}
+
+/// Value following `name` on the command line, e.g. `--frames 600`
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args();
+    args.find(|arg| arg == name)?;
+    args.next()
 }
 
 // Add the game's entities to our world
 fn setup(mut commands: Commands) {
     // Camera
-    commands.spawn(Camera2d);
+    commands.spawn(PlayAreaCamera);
 }
 
 fn handle_exit(
-    keyboard: Res<ButtonInput<KeyCode>>,
-    current_game_state: Res<State<GameState>>,
+    actions: Res<ButtonInput<Action>>,
+    current_game_state: Option<Res<State<GameState>>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
     mut next_game_state: ResMut<NextState<GameState>>,
     mut game_restart_event: EventWriter<GameRestartEvent>,
+    mut app_exit_events: EventWriter<AppExit>,
 ) {
-    if keyboard.just_released(KeyCode::KeyQ) {
-        std::process::exit(0);
+    if actions.just_released(Action::Quit) {
+        // Exit through the app, so settings and the replay get saved
+        app_exit_events.send(AppExit::Success);
+        return;
     }
-    if keyboard.just_released(KeyCode::Escape) {
+
+    // Everything else only applies to a game in progress
+    let Some(current_game_state) = current_game_state else {
+        return;
+    };
+    if actions.just_released(Action::Pause) {
         match current_game_state.get() {
             GameState::Playing => next_game_state.set(GameState::Paused),
             GameState::Paused => next_game_state.set(GameState::Playing),
             _ => (),
         }
     }
-    if keyboard.just_released(KeyCode::KeyR) && current_game_state.get() == &GameState::GameOver {
+    if actions.just_released(Action::Restart) && current_game_state.get() == &GameState::GameOver {
         next_game_state.set(GameState::Playing);
         game_restart_event.send_default();
     }
+    if actions.just_released(Action::MainMenu)
+        && matches!(
+            current_game_state.get(),
+            GameState::Paused | GameState::GameOver
+        )
+    {
+        // Leaving the game tears it down, so the next one starts like the first
+        next_app_state.set(AppState::Menu);
+    }
 }
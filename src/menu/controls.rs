@@ -0,0 +1,305 @@
+use bevy::prelude::*;
+
+use crate::{
+    input::{key_name, Action, CapturingKeys, InputBindings},
+    loading::UiAssets,
+    settings::Settings,
+    sprite_assets::SpriteAssets,
+    theme::Palette,
+};
+
+use super::{
+    menu::{MenuButtonAction, MenuState},
+    utils::{get_background_node, get_button_node, get_text_node},
+};
+
+const CONTROLS_FONT_SIZE: f32 = 20.0;
+const KEY_BACKGROUND: Color = Color::srgb(0.4, 0.4, 0.4);
+const KEY_BACKGROUND_CAPTURING: Color = Color::srgb(0.8, 0.8, 0.8);
+
+/// Lists the keys bound to each action. Clicking a key rebinds it to the next
+/// key pressed, and `+` adds another binding.
+pub struct ControlsPlugin;
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BindingCapture>()
+            .add_systems(OnEnter(MenuState::Controls), controls_setup)
+            .add_systems(OnExit(MenuState::Controls), stop_capturing)
+            .add_systems(
+                Update,
+                (
+                    start_capture,
+                    capture_key,
+                    reset_bindings,
+                    release_captured_keys,
+                    update_bindings_list.run_if(
+                        resource_changed::<Settings>.or(resource_changed::<BindingCapture>),
+                    ),
+                )
+                    .chain()
+                    .run_if(in_state(MenuState::Controls)),
+            );
+    }
+}
+
+/// The binding waiting for a key, and what happened to the last one
+#[derive(Resource, Default)]
+struct BindingCapture {
+    binding: Option<BindingButton>,
+    conflict: Option<(KeyCode, Action)>,
+}
+
+/// A bound key, or the button to add one when `slot` is `None`
+#[derive(Component, Clone, Copy, PartialEq)]
+struct BindingButton {
+    action: Action,
+    slot: Option<usize>,
+}
+
+#[derive(Component)]
+struct BindingsList;
+
+#[derive(Component)]
+struct ControlsStatus;
+
+#[derive(Component)]
+struct ResetBindingsButton;
+
+fn controls_setup(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    sprite_assets: Res<SpriteAssets>,
+) {
+    commands
+        .spawn((
+            StateScoped(MenuState::Controls),
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(get_background_node(&ui_assets))
+                .with_children(|p| {
+                    p.spawn((
+                        Text::new("Controls"),
+                        TextFont {
+                            font: ui_assets.font.clone(),
+                            font_size: 48.0,
+                            ..default()
+                        },
+                        TextColor(Palette::TEXT_PRIMARY),
+                        Node {
+                            margin: UiRect::new(
+                                Val::Px(16.0),
+                                Val::Px(16.0),
+                                Val::Px(32.0),
+                                Val::Px(12.0),
+                            ),
+                            ..default()
+                        },
+                    ));
+
+                    // Filled in by `update_bindings_list`
+                    p.spawn((
+                        BindingsList,
+                        Node {
+                            flex_direction: FlexDirection::Column,
+                            ..default()
+                        },
+                    ));
+
+                    p.spawn((
+                        ControlsStatus,
+                        Text::default(),
+                        TextFont {
+                            font: ui_assets.font.clone(),
+                            font_size: CONTROLS_FONT_SIZE,
+                            ..default()
+                        },
+                        TextColor(Palette::TEXT_PAUSED),
+                        Node {
+                            margin: UiRect::all(Val::Px(8.0)),
+                            ..default()
+                        },
+                    ));
+
+                    p.spawn((
+                        get_button_node(&sprite_assets, MenuButtonAction::Noop),
+                        ResetBindingsButton,
+                    ))
+                    .with_children(|p| {
+                        p.spawn(get_text_node(&ui_assets, "Reset"));
+                    });
+
+                    p.spawn(get_button_node(
+                        &sprite_assets,
+                        MenuButtonAction::BackToSettings,
+                    ))
+                    .with_children(|p| {
+                        p.spawn(get_text_node(&ui_assets, "Back"));
+                    });
+                });
+        });
+}
+
+fn start_capture(
+    mut commands: Commands,
+    interaction_query: Query<(&Interaction, &BindingButton), Changed<Interaction>>,
+    mut capture: ResMut<BindingCapture>,
+) {
+    for (interaction, binding) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            capture.binding = Some(*binding);
+            capture.conflict = None;
+            commands.insert_resource(CapturingKeys);
+        }
+    }
+}
+
+/// Bind the first key pressed while a binding is waiting. `Esc` cancels and
+/// `Delete` or `Backspace` removes the binding.
+fn capture_key(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut capture: ResMut<BindingCapture>,
+    mut settings: ResMut<Settings>,
+) {
+    let Some(binding) = capture.binding else {
+        return;
+    };
+    let Some(key) = keyboard.get_just_pressed().next().copied() else {
+        return;
+    };
+
+    match key {
+        KeyCode::Escape => (),
+        KeyCode::Delete | KeyCode::Backspace => {
+            if let Some(slot) = binding.slot {
+                settings.bindings.unbind(binding.action, slot);
+            }
+        }
+        _ => match settings.bindings.conflict(binding.action, key) {
+            Some(other) => capture.conflict = Some((key, other)),
+            None => settings.bindings.bind(binding.action, binding.slot, key),
+        },
+    }
+    capture.binding = None;
+}
+
+/// Keep the captured key away from the actions until it is let go, so
+/// binding `Q` doesn't also quit
+fn release_captured_keys(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    capture: Res<BindingCapture>,
+    capturing: Option<Res<CapturingKeys>>,
+) {
+    if capturing.is_some() && capture.binding.is_none() && keyboard.get_pressed().next().is_none() {
+        commands.remove_resource::<CapturingKeys>();
+    }
+}
+
+fn reset_bindings(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<ResetBindingsButton>)>,
+    mut capture: ResMut<BindingCapture>,
+    mut settings: ResMut<Settings>,
+) {
+    for interaction in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            settings.bindings = InputBindings::default();
+            *capture = BindingCapture::default();
+        }
+    }
+}
+
+fn stop_capturing(mut commands: Commands, mut capture: ResMut<BindingCapture>) {
+    *capture = BindingCapture::default();
+    commands.remove_resource::<CapturingKeys>();
+}
+
+fn update_bindings_list(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    settings: Res<Settings>,
+    capture: Res<BindingCapture>,
+    list: Single<Entity, With<BindingsList>>,
+    mut status: Single<&mut Text, With<ControlsStatus>>,
+) {
+    let text_font = TextFont {
+        font: ui_assets.font.clone(),
+        font_size: CONTROLS_FONT_SIZE,
+        ..default()
+    };
+
+    commands
+        .entity(*list)
+        .despawn_descendants()
+        .with_children(|p| {
+            for action in Action::ALL {
+                p.spawn(Node {
+                    align_items: AlignItems::Center,
+                    ..default()
+                })
+                .with_children(|p| {
+                    p.spawn((
+                        Text::new(action.label()),
+                        text_font.clone(),
+                        TextColor(Palette::TEXT_PRIMARY),
+                        Node {
+                            width: Val::Px(200.0),
+                            ..default()
+                        },
+                    ));
+
+                    let keys = settings.bindings.keys(action);
+                    let buttons = keys
+                        .iter()
+                        .enumerate()
+                        .map(|(slot, key)| (Some(slot), key_name(*key)))
+                        .chain([(None, "+".to_string())]);
+                    for (slot, label) in buttons {
+                        let binding = BindingButton { action, slot };
+                        let capturing = capture.binding == Some(binding);
+                        p.spawn((
+                            binding,
+                            Button,
+                            Node {
+                                min_width: Val::Px(40.0),
+                                padding: UiRect::axes(Val::Px(10.0), Val::Px(4.0)),
+                                margin: UiRect::all(Val::Px(4.0)),
+                                justify_content: JustifyContent::Center,
+                                ..default()
+                            },
+                            BackgroundColor(if capturing {
+                                KEY_BACKGROUND_CAPTURING
+                            } else {
+                                KEY_BACKGROUND
+                            }),
+                        ))
+                        .with_children(|p| {
+                            p.spawn((
+                                Text::new(if capturing { "...".to_string() } else { label }),
+                                text_font.clone(),
+                                TextColor(Palette::TEXT_PRIMARY),
+                            ));
+                        });
+                    }
+                });
+            }
+        });
+
+    status.0 = match (capture.binding, capture.conflict) {
+        (Some(binding), _) => format!(
+            "Press a key for {}, `Esc` to cancel or `Delete` to remove it",
+            binding.action.label()
+        ),
+        (None, Some((key, other))) => {
+            format!("`{}` is already bound to {}", key_name(key), other.label())
+        }
+        (None, None) => "Click a key to change it or + to add one".to_string(),
+    };
+}
@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::{systems::despawn_screen, theme::Palette, AppState};
+use crate::{loading::UiAssets, sprite_assets::SpriteAssets, theme::Palette};
 
 use super::{
     menu::{MenuButtonAction, MenuState},
@@ -11,9 +11,7 @@ pub struct MainMenuPlugin;
 impl Plugin for MainMenuPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(OnEnter(MenuState::Main), main_menu_setup)
-            .add_systems(Update, main_menu.run_if(in_state(MenuState::Main)))
-            .add_systems(OnExit(MenuState::Main), despawn_screen::<MainMenuScreen>)
-            .add_systems(OnExit(AppState::Menu), despawn_screen::<MainMenuScreen>);
+            .add_systems(Update, main_menu.run_if(in_state(MenuState::Main)));
     }
 }
 
@@ -22,15 +20,16 @@ struct MainMenuScreen;
 
 fn main_menu_setup(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
+    ui_assets: Res<UiAssets>,
+    sprite_assets: Res<SpriteAssets>,
 ) {
-    let text_font = asset_server.load("../assets/atari_games.ttf");
+    let text_font = ui_assets.font.clone();
 
     // Add menu entities
     commands
         .spawn((
             MainMenuScreen,
+            StateScoped(MenuState::Main),
             Node {
                 width: Val::Percent(100.0),
                 height: Val::Percent(100.0),
@@ -41,9 +40,9 @@ fn main_menu_setup(
         ))
         .with_children(|parent| {
             parent
-                .spawn(get_background_node(&asset_server))
+                .spawn(get_background_node(&ui_assets))
                 .with_children(|p| {
-                    // get_text_node(&asset_server, "Space Shooter"),
+                    // get_text_node(&ui_assets, "Space Shooter"),
                     p.spawn((
                         Text::new("Space Shooter"),
                         TextFont {
@@ -63,32 +62,20 @@ fn main_menu_setup(
                         },
                     ));
 
-                    p.spawn(get_button_node(
-                        &asset_server,
-                        &mut texture_atlases,
-                        MenuButtonAction::Play,
-                    ))
-                    .with_children(|p| {
-                        p.spawn(get_text_node(&asset_server, "New Game"));
-                    });
+                    p.spawn(get_button_node(&sprite_assets, MenuButtonAction::NewGame))
+                        .with_children(|p| {
+                            p.spawn(get_text_node(&ui_assets, "New Game"));
+                        });
 
-                    p.spawn(get_button_node(
-                        &asset_server,
-                        &mut texture_atlases,
-                        MenuButtonAction::Settings,
-                    ))
-                    .with_children(|p| {
-                        p.spawn(get_text_node(&asset_server, "Settings"));
-                    });
+                    p.spawn(get_button_node(&sprite_assets, MenuButtonAction::Settings))
+                        .with_children(|p| {
+                            p.spawn(get_text_node(&ui_assets, "Settings"));
+                        });
 
-                    p.spawn(get_button_node(
-                        &asset_server,
-                        &mut texture_atlases,
-                        MenuButtonAction::Quit,
-                    ))
-                    .with_children(|p| {
-                        p.spawn(get_text_node(&asset_server, "Quit"));
-                    });
+                    p.spawn(get_button_node(&sprite_assets, MenuButtonAction::Quit))
+                        .with_children(|p| {
+                            p.spawn(get_text_node(&ui_assets, "Quit"));
+                        });
                 });
         });
 }
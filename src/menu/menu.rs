@@ -1,16 +1,36 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, ui::UiSystem};
 
-use crate::AppState;
+use crate::{
+    input::{Action, ActionSystem},
+    theme::Palette,
+    AppState,
+};
 
+use super::controls::ControlsPlugin;
 use super::main_menu::MainMenuPlugin;
+use super::new_game::NewGamePlugin;
 use super::settings::SettingsPlugin;
 
 pub struct MenuPlugin;
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
-        app.init_state::<MenuState>()
-            .add_systems(OnEnter(AppState::Menu), menu_setup)
-            .add_plugins((MainMenuPlugin, SettingsPlugin))
+        app.add_sub_state::<MenuState>()
+            .enable_state_scoped_entities::<MenuState>()
+            .add_plugins((
+                MainMenuPlugin,
+                NewGamePlugin,
+                SettingsPlugin,
+                ControlsPlugin,
+            ))
+            // Presses from navigating are seen by every menu system in
+            // `Update`, just like clicks
+            .add_systems(
+                PreUpdate,
+                navigate_menu
+                    .after(ActionSystem)
+                    .after(UiSystem::Focus)
+                    .run_if(in_state(AppState::Menu)),
+            )
             .add_systems(
                 Update,
                 (menu_action, button_system).run_if(in_state(AppState::Menu)),
@@ -18,12 +38,16 @@ impl Plugin for MenuPlugin {
     }
 }
 
-// State used for the current menu screen
-#[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
+// State used for the current menu screen, starting at the main menu every time
+// the menu is entered
+#[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, SubStates)]
+#[source(AppState = AppState::Menu)]
 pub enum MenuState {
     #[default]
     Main,
+    NewGame,
     Settings,
+    Controls,
     //SettingsDisplay,
     //SettingsSound,
 }
@@ -31,23 +55,92 @@ pub enum MenuState {
 // All actions that can be triggered from a button click
 #[derive(Component, Default)]
 pub enum MenuButtonAction {
+    NewGame,
     Play,
     Settings,
+    Controls,
     // SettingsDisplay,
     // SettingsSound,
     BackToMainMenu,
-    //BackToSettings,
+    BackToSettings,
     Quit,
     #[default]
     Noop,
 }
 
-// Tag component used to mark which setting is currently selected
+// Tag component used to mark the button that gamepad and keyboard navigation
+// is on
+#[derive(Component)]
+pub(super) struct SelectedOption;
+
+// Tag component for buttons that are changed with left and right when
+// selected instead of being pressed, e.g. a slider
 #[derive(Component)]
-struct SelectedOption;
+pub(super) struct AdjustedOption;
+
+/// Move the selection between the buttons on screen with up and down, and
+/// press the selected one with fire. Nothing is selected until up or down is
+/// pressed, so the mouse alone never shows a selection.
+fn navigate_menu(
+    mut commands: Commands,
+    actions: Res<ButtonInput<Action>>,
+    buttons: Query<(Entity, &GlobalTransform, Has<SelectedOption>), With<Button>>,
+    pressable: Query<Entity, (With<SelectedOption>, Without<AdjustedOption>)>,
+    mut interactions: Query<&mut Interaction>,
+    mut pressed: Local<Option<Entity>>,
+) {
+    // A press only lasts a frame, as if the button had been clicked
+    if let Some(entity) = pressed.take() {
+        if let Ok(mut interaction) = interactions.get_mut(entity) {
+            if *interaction == Interaction::Pressed {
+                *interaction = Interaction::None;
+            }
+        }
+    }
+
+    if actions.just_pressed(Action::Fire) {
+        if let Ok(entity) = pressable.get_single() {
+            if let Ok(mut interaction) = interactions.get_mut(entity) {
+                *interaction = Interaction::Pressed;
+                *pressed = Some(entity);
+            }
+        }
+    }
+
+    let step: isize = match (
+        actions.just_pressed(Action::MoveUp),
+        actions.just_pressed(Action::MoveDown),
+    ) {
+        (true, false) => -1,
+        (false, true) => 1,
+        _ => return,
+    };
+
+    // Screen order, top to bottom and then left to right
+    let mut buttons: Vec<_> = buttons.iter().collect();
+    buttons.sort_by(|(_, a, _), (_, b, _)| {
+        let (a, b) = (a.translation(), b.translation());
+        a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x))
+    });
+    if buttons.is_empty() {
+        return;
+    }
 
-fn menu_setup(mut menu_state: ResMut<NextState<MenuState>>) {
-    menu_state.set(MenuState::Main);
+    let count = buttons.len() as isize;
+    let next = match buttons.iter().position(|(_, _, selected)| *selected) {
+        Some(current) => {
+            commands
+                .entity(buttons[current].0)
+                .remove::<(SelectedOption, Outline)>();
+            (current as isize + step).rem_euclid(count)
+        }
+        None if step > 0 => 0,
+        None => count - 1,
+    };
+    commands.entity(buttons[next as usize].0).insert((
+        SelectedOption,
+        Outline::new(Val::Px(2.0), Val::Px(2.0), Palette::TEXT_PRIMARY),
+    ));
 }
 
 fn menu_action(
@@ -65,12 +158,18 @@ fn menu_action(
                 MenuButtonAction::Quit => {
                     app_exit_events.send(AppExit::Success);
                 }
+                MenuButtonAction::NewGame => {
+                    menu_state.set(MenuState::NewGame);
+                }
                 MenuButtonAction::Play => {
                     app_state.set(AppState::Game);
                 }
                 MenuButtonAction::Settings => {
                     menu_state.set(MenuState::Settings);
                 }
+                MenuButtonAction::Controls => {
+                    menu_state.set(MenuState::Controls);
+                }
 
                 // MenuButtonAction::SettingsDisplay => {
                 //     menu_state.set(MainMenuState::SettingsDisplay);
@@ -80,9 +179,10 @@ fn menu_action(
                 // }
                 MenuButtonAction::BackToMainMenu => {
                     menu_state.set(MenuState::Main);
-                } // MenuButtonAction::BackToSettings => {
-                //     menu_state.set(MainMenuState::Settings);
-                // }
+                }
+                MenuButtonAction::BackToSettings => {
+                    menu_state.set(MenuState::Settings);
+                }
                 MenuButtonAction::Noop => (),
             }
         }
@@ -1,4 +1,6 @@
+mod controls;
 mod main_menu;
 pub mod menu;
+mod new_game;
 mod settings;
 mod utils;
@@ -0,0 +1,156 @@
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState,
+    },
+    prelude::*,
+};
+
+use crate::{loading::UiAssets, settings::Settings, sprite_assets::SpriteAssets, theme::Palette};
+
+use super::{
+    menu::{MenuButtonAction, MenuState},
+    utils::{get_background_node, get_button_node, get_text_node},
+};
+
+pub struct NewGamePlugin;
+impl Plugin for NewGamePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(MenuState::NewGame), new_game_setup)
+            .add_systems(
+                Update,
+                (
+                    edit_seed,
+                    update_seed_text.run_if(resource_changed::<Settings>),
+                )
+                    .chain()
+                    .run_if(in_state(MenuState::NewGame)),
+            );
+    }
+}
+
+#[derive(Component)]
+struct NewGameScreen;
+
+#[derive(Component)]
+struct SeedText;
+
+fn new_game_setup(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    sprite_assets: Res<SpriteAssets>,
+    settings: Res<Settings>,
+) {
+    let text_font = ui_assets.font.clone();
+
+    // Add menu entities
+    commands
+        .spawn((
+            NewGameScreen,
+            StateScoped(MenuState::NewGame),
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(get_background_node(&ui_assets))
+                .with_children(|p| {
+                    p.spawn((
+                        Text::new("New Game"),
+                        TextFont {
+                            font: text_font.clone(),
+                            font_size: 48.0,
+                            ..default()
+                        },
+                        TextColor(Palette::TEXT_PRIMARY),
+                        Node {
+                            margin: UiRect::new(
+                                Val::Px(16.0),
+                                Val::Px(16.0),
+                                Val::Px(32.0),
+                                Val::Px(12.0),
+                            ),
+                            ..default()
+                        },
+                    ));
+
+                    // Seed input, typed with the number keys
+                    p.spawn(get_text_node(&ui_assets, "Seed"));
+                    p.spawn((
+                        SeedText,
+                        Text::new(seed_label(settings.seed)),
+                        TextFont {
+                            font: text_font.clone(),
+                            font_size: 28.0,
+                            ..default()
+                        },
+                        TextColor(Palette::TEXT_PAUSED),
+                        Node {
+                            margin: UiRect::all(Val::Px(8.0)),
+                            ..default()
+                        },
+                    ));
+
+                    p.spawn(get_button_node(&sprite_assets, MenuButtonAction::Play))
+                        .with_children(|p| {
+                            p.spawn(get_text_node(&ui_assets, "Start"));
+                        });
+
+                    p.spawn(get_button_node(
+                        &sprite_assets,
+                        MenuButtonAction::BackToMainMenu,
+                    ))
+                    .with_children(|p| {
+                        p.spawn(get_text_node(&ui_assets, "Back"));
+                    });
+                });
+        });
+}
+
+fn edit_seed(mut keyboard_events: EventReader<KeyboardInput>, mut settings: ResMut<Settings>) {
+    for event in keyboard_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+
+        match &event.logical_key {
+            Key::Character(character) => {
+                let digits = format!(
+                    "{}{}",
+                    settings
+                        .seed
+                        .map(|seed| seed.to_string())
+                        .unwrap_or_default(),
+                    character
+                );
+                // Ignore anything that isn't a digit or would overflow
+                if let Ok(seed) = digits.parse() {
+                    settings.seed = Some(seed);
+                }
+            }
+            Key::Backspace => {
+                settings.seed = settings.seed.and_then(|seed| {
+                    let digits = seed.to_string();
+                    digits[..digits.len() - 1].parse().ok()
+                });
+            }
+            _ => (),
+        }
+    }
+}
+
+fn update_seed_text(settings: Res<Settings>, mut seed_text: Single<&mut Text, With<SeedText>>) {
+    seed_text.0 = seed_label(settings.seed);
+}
+
+fn seed_label(seed: Option<u64>) -> String {
+    match seed {
+        Some(seed) => seed.to_string(),
+        None => "Random".to_string(),
+    }
+}
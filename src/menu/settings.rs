@@ -1,9 +1,18 @@
-use bevy::{prelude::*, ui::UiRect};
+use bevy::{
+    prelude::*,
+    ui::{RelativeCursorPosition, UiRect},
+};
 
-use crate::{settings::Settings, systems::despawn_screen, theme::Palette, AppState};
+use crate::{
+    input::Action,
+    loading::UiAssets,
+    settings::{Settings, MAX_GAMEPAD_DEADZONE},
+    sprite_assets::SpriteAssets,
+    theme::Palette,
+};
 
 use super::{
-    menu::{MenuButtonAction, MenuState},
+    menu::{AdjustedOption, MenuButtonAction, MenuState, SelectedOption},
     utils::{get_background_node, get_button_node, get_text_node},
 };
 
@@ -11,39 +20,78 @@ pub struct SettingsPlugin;
 impl Plugin for SettingsPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(OnEnter(MenuState::Settings), settings_setup)
-            .add_systems(Update, settings.run_if(in_state(MenuState::Settings)))
             .add_systems(
-                OnExit(MenuState::Settings),
-                despawn_screen::<SettingsScreen>,
-            )
-            .add_systems(OnExit(AppState::Menu), despawn_screen::<SettingsScreen>);
+                Update,
+                (
+                    settings,
+                    adjust_selected_slider,
+                    toggle_control_scheme,
+                    (update_sliders, update_control_scheme_text)
+                        .run_if(resource_changed::<Settings>),
+                )
+                    .chain()
+                    .run_if(in_state(MenuState::Settings)),
+            );
     }
 }
 
 #[derive(Component)]
 struct SettingsScreen;
 
-#[derive(Component)]
-enum VolumeControl {
-    Music,
-    Effects,
+/// How far a slider moves for each press of left or right
+const SLIDER_STEP: f32 = 0.1;
+
+#[derive(Component, Clone, Copy)]
+enum SliderSetting {
+    MusicVolume,
+    EffectVolume,
+    GamepadDeadzone,
+}
+
+impl SliderSetting {
+    /// Position of the slider, from 0 to 1
+    fn get(self, settings: &Settings) -> f32 {
+        match self {
+            SliderSetting::MusicVolume => settings.music_volume,
+            SliderSetting::EffectVolume => settings.effect_volume,
+            SliderSetting::GamepadDeadzone => settings.gamepad_deadzone / MAX_GAMEPAD_DEADZONE,
+        }
+    }
+
+    fn set(self, settings: &mut Settings, position: f32) {
+        let position = position.clamp(0.0, 1.0);
+        match self {
+            SliderSetting::MusicVolume => settings.set_music_volume(position),
+            SliderSetting::EffectVolume => settings.set_effect_volume(position),
+            SliderSetting::GamepadDeadzone => {
+                settings.set_gamepad_deadzone(position * MAX_GAMEPAD_DEADZONE)
+            }
+        }
+    }
 }
 
 #[derive(Component)]
 struct Slider;
 
+#[derive(Component)]
+struct ControlSchemeButton;
+
+#[derive(Component)]
+struct ControlSchemeText;
+
 fn settings_setup(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
+    ui_assets: Res<UiAssets>,
+    sprite_assets: Res<SpriteAssets>,
     settings: Res<Settings>,
 ) {
-    let text_font = asset_server.load("../assets/atari_games.ttf");
+    let text_font = ui_assets.font.clone();
 
     // Add menu entities
     commands
         .spawn((
             SettingsScreen,
+            StateScoped(MenuState::Settings),
             Node {
                 width: Val::Percent(100.0),
                 height: Val::Percent(100.0),
@@ -54,7 +102,7 @@ fn settings_setup(
         ))
         .with_children(|parent| {
             parent
-                .spawn(get_background_node(&asset_server))
+                .spawn(get_background_node(&ui_assets))
                 .with_children(|p| {
                     p.spawn((
                         Text::new("Settings"),
@@ -75,110 +123,132 @@ fn settings_setup(
                         },
                     ));
 
-                    // Music Volume Control
-                    p.spawn(get_text_node(&asset_server, "Music Volume"));
-                    p.spawn((
-                        VolumeControl::Music,
-                        Button,
-                        Interaction::default(),
-                        Node {
-                            width: Val::Px(200.0),
-                            height: Val::Px(20.0),
-                            margin: UiRect::all(Val::Px(8.0)),
-                            ..default()
-                        },
-                        BackgroundColor(Color::srgb(0.4, 0.4, 0.4)),
-                    ))
-                    .with_children(|p| {
+                    for (label, setting) in [
+                        ("Music Volume", SliderSetting::MusicVolume),
+                        ("Effects Volume", SliderSetting::EffectVolume),
+                        ("Stick Deadzone", SliderSetting::GamepadDeadzone),
+                    ] {
+                        p.spawn(get_text_node(&ui_assets, label));
                         p.spawn((
-                            Slider,
+                            setting,
+                            Button,
+                            AdjustedOption,
+                            Interaction::default(),
+                            RelativeCursorPosition::default(),
                             Node {
-                                width: Val::Percent(100.0 * settings.music_volume), // Initial value
-                                height: Val::Percent(100.0),
+                                width: Val::Px(200.0),
+                                height: Val::Px(20.0),
+                                margin: UiRect::all(Val::Px(8.0)),
                                 ..default()
                             },
-                            BackgroundColor(Color::srgb(1.0, 1.0, 1.0)),
-                        ));
-                    });
+                            BackgroundColor(Color::srgb(0.4, 0.4, 0.4)),
+                        ))
+                        .with_children(|p| {
+                            p.spawn((
+                                Slider,
+                                Node {
+                                    width: Val::Percent(100.0 * setting.get(&settings)), // Initial value
+                                    height: Val::Percent(100.0),
+                                    ..default()
+                                },
+                                BackgroundColor(Color::srgb(1.0, 1.0, 1.0)),
+                            ));
+                        });
+                    }
 
-                    // Effects Volume Control
-                    p.spawn(get_text_node(&asset_server, "Effects Volume"));
                     p.spawn((
-                        VolumeControl::Effects,
-                        Button,
-                        Interaction::default(),
-                        Node {
-                            width: Val::Px(200.0),
-                            height: Val::Px(20.0),
-                            margin: UiRect::all(Val::Px(8.0)),
-                            ..default()
-                        },
-                        BackgroundColor(Color::srgb(0.4, 0.4, 0.4)),
+                        get_button_node(&sprite_assets, MenuButtonAction::Noop),
+                        ControlSchemeButton,
                     ))
                     .with_children(|p| {
                         p.spawn((
-                            Slider,
-                            Node {
-                                width: Val::Percent(100.0 * settings.effect_volume), // Initial value
-                                height: Val::Percent(100.0),
-                                ..default()
-                            },
-                            BackgroundColor(Color::srgb(1.0, 1.0, 1.0)),
+                            get_text_node(&ui_assets, &control_scheme_label(&settings)),
+                            ControlSchemeText,
                         ));
                     });
 
+                    p.spawn(get_button_node(&sprite_assets, MenuButtonAction::Controls))
+                        .with_children(|p| {
+                            p.spawn(get_text_node(&ui_assets, "Controls"));
+                        });
+
                     p.spawn(get_button_node(
-                        &asset_server,
-                        &mut texture_atlases,
+                        &sprite_assets,
                         MenuButtonAction::BackToMainMenu,
                     ))
                     .with_children(|p| {
-                        p.spawn(get_text_node(&asset_server, "Back"));
+                        p.spawn(get_text_node(&ui_assets, "Back"));
                     });
                 });
         });
 }
 
 fn settings(
-    interaction_query: Query<(
-        &Interaction,
-        &VolumeControl,
-        &Children,
-        &GlobalTransform,
-        &ComputedNode,
-    )>,
-    mut node_query: Query<&mut Node>,
+    interaction_query: Query<(&Interaction, &SliderSetting, &RelativeCursorPosition)>,
     mut settings: ResMut<Settings>,
-    windows: Query<&Window>,
 ) {
-    let window = windows.single();
-
-    for (interaction, volume_control, children, global_transform, computed_node) in
-        interaction_query.iter()
-    {
+    for (interaction, setting, relative_cursor_position) in interaction_query.iter() {
         if let Interaction::Pressed = interaction {
-            if let Some(cursor_position) = window.cursor_position() {
-                let node_width = computed_node.size()[0] * computed_node.inverse_scale_factor();
-                let half_width = node_width / 2.0;
-                let left_side = global_transform.translation().x
-                    * computed_node.inverse_scale_factor()
-                    - half_width;
-                let relative_x = cursor_position.x - left_side;
-                let volume = (relative_x / node_width).clamp(0.0, 1.0);
-
-                // Update the slider visual
-                if let Some(slider) = children.first() {
-                    if let Ok(mut slider_node) = node_query.get_mut(*slider) {
-                        slider_node.width = Val::Percent(volume * 100.0);
-                    }
-                }
-
-                // Update the settings
-                match volume_control {
-                    VolumeControl::Music => settings.set_music_volume(volume),
-                    VolumeControl::Effects => settings.set_effect_volume(volume),
-                }
+            // Cursor position relative to the slider, which also accounts for
+            // the camera being letterboxed inside the window
+            if let Some(cursor_position) = relative_cursor_position.normalized {
+                setting.set(&mut settings, cursor_position.x);
             }
         }
     }
 }
+
+/// Move the selected slider with left and right, for gamepads and keyboards
+fn adjust_selected_slider(
+    actions: Res<ButtonInput<Action>>,
+    selected: Option<Single<&SliderSetting, With<SelectedOption>>>,
+    mut settings: ResMut<Settings>,
+) {
+    let Some(setting) = selected else {
+        return;
+    };
+    let step = match (
+        actions.just_pressed(Action::MoveLeft),
+        actions.just_pressed(Action::MoveRight),
+    ) {
+        (true, false) => -SLIDER_STEP,
+        (false, true) => SLIDER_STEP,
+        _ => return,
+    };
+    let value = setting.get(&settings);
+    setting.set(&mut settings, value + step);
+}
+
+fn update_sliders(
+    settings: Res<Settings>,
+    slider_query: Query<(&SliderSetting, &Children)>,
+    mut node_query: Query<&mut Node, With<Slider>>,
+) {
+    for (setting, children) in &slider_query {
+        if let Some(mut slider_node) = children.first().and_then(|s| node_query.get_mut(*s).ok()) {
+            slider_node.width = Val::Percent(setting.get(&settings) * 100.0);
+        }
+    }
+}
+
+fn toggle_control_scheme(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<ControlSchemeButton>)>,
+    mut settings: ResMut<Settings>,
+) {
+    for interaction in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            settings.control_scheme = settings.control_scheme.next();
+        }
+    }
+}
+
+fn update_control_scheme_text(
+    settings: Res<Settings>,
+    mut text: Single<&mut Text, With<ControlSchemeText>>,
+) {
+    text.0 = control_scheme_label(&settings);
+}
+
+fn control_scheme_label(settings: &Settings) -> String {
+    format!("Steer: {}", settings.control_scheme.label())
+}
@@ -1,6 +1,6 @@
 use bevy::{prelude::*, ui::widget::NodeImageMode};
 
-use crate::theme::Palette;
+use crate::{loading::UiAssets, sprite_assets::SpriteAssets, theme::Palette};
 
 use super::menu::MenuButtonAction;
 
@@ -45,8 +45,8 @@ impl Default for MenuText {
     }
 }
 
-pub fn get_background_node(asset_server: &Res<AssetServer>) -> impl Bundle {
-    let background_image = asset_server.load("../assets/window_background.png");
+pub fn get_background_node(ui_assets: &UiAssets) -> impl Bundle {
+    let background_image = ui_assets.window_background.clone();
 
     let background_slicer = TextureSlicer {
         border: BorderRect::square(104.0),
@@ -70,16 +70,7 @@ pub fn get_background_node(asset_server: &Res<AssetServer>) -> impl Bundle {
     )
 }
 
-pub fn get_button_node(
-    asset_server: &Res<AssetServer>,
-    texture_atlases: &mut ResMut<Assets<TextureAtlasLayout>>,
-    action: MenuButtonAction,
-) -> impl Bundle {
-    let button_image = asset_server.load("../assets/button-background.png");
-
-    let button_atlas = TextureAtlasLayout::from_grid(UVec2::splat(36), 2, 1, None, None);
-    let button_atlas_handle = texture_atlases.add(button_atlas);
-
+pub fn get_button_node(sprite_assets: &SpriteAssets, action: MenuButtonAction) -> impl Bundle {
     let button_slicer = TextureSlicer {
         border: BorderRect::square(17.0),
         center_scale_mode: SliceScaleMode::Stretch,
@@ -89,8 +80,8 @@ pub fn get_button_node(
 
     MenuButton {
         image: ImageNode::from_atlas_image(
-            button_image.clone(),
-            TextureAtlas::from(button_atlas_handle.clone()),
+            sprite_assets.button.image.clone(),
+            sprite_assets.button.atlas(0),
         )
         .with_mode(NodeImageMode::Sliced(button_slicer.clone())),
         action,
@@ -98,8 +89,8 @@ pub fn get_button_node(
     }
 }
 
-pub fn get_text_node(asset_server: &Res<AssetServer>, text: &str) -> impl Bundle {
-    let text_font = asset_server.load("../assets/atari_games.ttf");
+pub fn get_text_node(ui_assets: &UiAssets, text: &str) -> impl Bundle {
+    let text_font = ui_assets.font.clone();
 
     let button_text_font = TextFont {
         font: text_font.clone(),
@@ -1,4 +1,6 @@
-use crate::{game_state::GameState, theme::Palette};
+use crate::{
+    game_state::GameState, input::Action, loading::UiAssets, settings::Settings, theme::Palette,
+};
 use bevy::prelude::*;
 
 const PAUSED_FONT_SIZE: f32 = 80.0;
@@ -6,20 +8,20 @@ const PAUSED_FONT_SIZE: f32 = 80.0;
 pub struct PausedPlugin;
 impl Plugin for PausedPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Paused), setup_paused)
-            .add_systems(OnExit(GameState::Paused), cleanup_paused);
+        app.add_systems(OnEnter(GameState::Paused), setup_paused);
     }
 }
 
 #[derive(Component)]
 struct PausedText;
 
-fn setup_paused(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let font = asset_server.load("../assets/atari_games.ttf");
+fn setup_paused(mut commands: Commands, ui_assets: Res<UiAssets>, settings: Res<Settings>) {
+    let font = ui_assets.font.clone();
 
     commands
         .spawn((
             PausedText,
+            StateScoped(GameState::Paused),
             Node {
                 height: Val::Percent(100.0),
                 width: Val::Percent(100.0),
@@ -41,7 +43,10 @@ fn setup_paused(mut commands: Commands, asset_server: Res<AssetServer>) {
             ));
 
             parent.spawn((
-                Text::new("Press `Esc` to unpause"),
+                Text::new(format!(
+                    "Press {} to unpause",
+                    settings.bindings.describe(Action::Pause)
+                )),
                 TextFont {
                     font: font.clone(),
                     font_size: 24.0,
@@ -56,11 +61,25 @@ fn setup_paused(mut commands: Commands, asset_server: Res<AssetServer>) {
                     ..default()
                 },
             ));
-        });
-}
 
-fn cleanup_paused(mut commands: Commands, query: Query<Entity, With<PausedText>>) {
-    for entity in query.iter() {
-        commands.entity(entity).despawn_recursive();
-    }
+            parent.spawn((
+                Text::new(format!(
+                    "Press {} for the main menu",
+                    settings.bindings.describe(Action::MainMenu)
+                )),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Palette::TEXT_PRIMARY),
+                Node {
+                    margin: UiRect {
+                        top: Val::Px(20.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ));
+        });
 }
@@ -0,0 +1,117 @@
+use bevy::{
+    prelude::*,
+    render::camera::{CameraUpdateSystem, ScalingMode, Viewport},
+};
+
+/// World units per pixel of sprite art. Sprites are drawn at twice their size.
+pub const PIXEL_SCALE: f32 = 2.0;
+
+/// The rectangle gameplay takes place in, centered on the origin.
+///
+/// Gameplay systems read their bounds from here instead of the window so the
+/// simulation can also run without one. Its size is fixed regardless of the
+/// window; the camera scales it to fit.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct PlayArea {
+    pub size: Vec2,
+}
+
+impl PlayArea {
+    pub fn width(&self) -> f32 {
+        self.size.x
+    }
+
+    pub fn height(&self) -> f32 {
+        self.size.y
+    }
+
+    pub fn half_size(&self) -> Vec2 {
+        self.size / 2.0
+    }
+
+    /// Size of the play area in sprite pixels
+    pub fn native_resolution(&self) -> Vec2 {
+        self.size / PIXEL_SCALE
+    }
+}
+
+impl Default for PlayArea {
+    fn default() -> Self {
+        // NOTE: Common resolution that most monitors scale well with is 640x360px
+        Self {
+            size: Vec2::new(640.0, 360.0) * PIXEL_SCALE,
+        }
+    }
+}
+
+/// Marks the camera that renders the play area
+#[derive(Component)]
+#[require(Camera2d)]
+pub struct PlayAreaCamera;
+
+pub struct PlayAreaPlugin;
+impl Plugin for PlayAreaPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayArea>()
+            .add_systems(PostUpdate, fit_camera_to_window.before(CameraUpdateSystem));
+    }
+}
+
+/// Scale the play area to the window, keeping its aspect ratio and leaving the
+/// remaining space as bars on the sides
+fn fit_camera_to_window(
+    play_area: Res<PlayArea>,
+    windows: Query<&Window>,
+    mut camera_query: Query<(&mut Camera, &mut OrthographicProjection), With<PlayAreaCamera>>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+
+    for (mut camera, mut projection) in &mut camera_query {
+        let fits_play_area = matches!(
+            projection.scaling_mode,
+            ScalingMode::Fixed { width, height }
+                if width == play_area.width() && height == play_area.height()
+        );
+        if !fits_play_area {
+            projection.scaling_mode = ScalingMode::Fixed {
+                width: play_area.width(),
+                height: play_area.height(),
+            };
+        }
+
+        let Some(viewport) = letterbox_viewport(window.physical_size(), &play_area) else {
+            continue;
+        };
+        let unchanged = camera.viewport.as_ref().is_some_and(|current| {
+            current.physical_position == viewport.physical_position
+                && current.physical_size == viewport.physical_size
+        });
+        if !unchanged {
+            camera.viewport = Some(viewport);
+        }
+    }
+}
+
+/// The largest centered viewport that fits the play area inside the window.
+///
+/// Uses whole multiples of the native resolution to keep the pixel art crisp,
+/// unless the window is too small to fit even one.
+fn letterbox_viewport(window_size: UVec2, play_area: &PlayArea) -> Option<Viewport> {
+    if window_size.min_element() == 0 {
+        // The window is minimized
+        return None;
+    }
+
+    let native_resolution = play_area.native_resolution();
+    let fit = (window_size.as_vec2() / native_resolution).min_element();
+    let scale = if fit >= 1.0 { fit.floor() } else { fit };
+    let size = (native_resolution * scale).as_uvec2().max(UVec2::ONE);
+
+    Some(Viewport {
+        physical_position: window_size.saturating_sub(size) / 2,
+        physical_size: size.min(window_size),
+        ..default()
+    })
+}
@@ -1,28 +1,35 @@
-use bevy::{
-    audio::*,
-    image::{ImageLoaderSettings, ImageSampler},
-    prelude::*,
-};
+use bevy::{audio::*, prelude::*};
 
 use crate::{
-    audio::GameSounds,
-    collisions::Collider,
-    components::{Bounds, Bullet, Health, MovementInput, MovementSpeed, PlayerStats, Shoot},
+    balance::Balance,
+    buffs::ActiveBuffs,
+    collisions::{Collider, PlayerDamagedEvent, PlayerHurtEvent},
+    components::{
+        Bounds, Bullet, Health, MaxHealth, MovementInput, MovementSpeed, PlayerStats, Shoot,
+        WeaponLevel,
+    },
+    game::{despawn_all, GameplaySet, StartRun, StartRunSet},
     game_state::GameState,
+    input::PlayerInput,
+    interpolation::InterpolatedTranslation,
+    loading::SoundAssets,
+    play_area::PlayArea,
     settings::Settings,
     sprite_animation::{update_animations, AnimationConfig},
+    sprite_assets::SpriteAssets,
     AppState,
 };
 
-const SPRITE_SHEET_PATH: &str = "../assets/ship.png";
-const SPRITE_SIZE: UVec2 = UVec2::new(16, 24);
-const SPRITE_COLUMNS: u32 = 2;
-const SPRITE_ROWS: u32 = 5;
 const SPRITE_FPS: u8 = 12;
 
-// TODO: start very slow and gain speed with leveling up
-const PLAYER_SPEED: f32 = 200.0;
-const PLAYER_SHOOT_COOLDOWN: f32 = 0.4;
+/// Health at the start of a run, and the most that healing restores
+const MAX_HEALTH: i32 = 10;
+
+/// Seconds the player is shown or hidden for while blinking
+const BLINK_INTERVAL: f32 = 0.08;
+
+/// How quickly a knockback slows down, per second
+const KNOCKBACK_DECAY: f32 = 8.0;
 
 // Sprite indices for different states
 const IDLE_SPRITES: (usize, usize) = (0, 1);
@@ -31,46 +38,77 @@ const MOVE_LEFT_SPRITES: (usize, usize) = (4, 5);
 const TRANSITION_RIGHT_SPRITES: (usize, usize) = (6, 7);
 const MOVE_RIGHT_SPRITES: (usize, usize) = (8, 9);
 
-const BULLET_SPRITE_PATH: &str = "../assets/laser-bolts.png";
-const BULLET_SPRITE_SIZE: UVec2 = UVec2::new(16, 16);
-const BULLET_SPRITE_COLUMNS: u32 = 2;
-const BULLET_SPRITE_ROWS: u32 = 2;
-const BULLET_SPEED: f32 = 500.0;
+/// The bolts fired at each weapon level, as a sideways offset from the ship and
+/// an angle in degrees from straight up, clockwise
+const SHOT_PATTERNS: [&[(f32, f32)]; WeaponLevel::MAX as usize] = [
+    // Single shot
+    &[(0.0, 0.0)],
+    // Twin shot
+    &[(-10.0, 0.0), (10.0, 0.0)],
+    // Three-way spread
+    &[(0.0, 0.0), (-6.0, -15.0), (6.0, 15.0)],
+    // Wide spread with side bolts
+    &[
+        (-10.0, 0.0),
+        (10.0, 0.0),
+        (-8.0, -25.0),
+        (8.0, 25.0),
+        (-16.0, -80.0),
+        (16.0, 80.0),
+    ],
+];
 
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(AppState::Game), spawn_player)
-            .add_systems(OnExit(AppState::Game), cleanup_player)
-            .add_systems(
-                OnTransition {
-                    exited: GameState::GameOver,
-                    entered: GameState::Playing,
-                },
-                (cleanup_player, spawn_player).chain(),
-            )
-            .add_systems(
-                Update,
+        app.add_systems(
+            StartRun,
+            (
+                (despawn_all::<Player>, despawn_all::<Bullet>).in_set(StartRunSet::Despawn),
+                spawn_player.in_set(StartRunSet::Spawn),
+            ),
+        )
+        .add_systems(
+            FixedUpdate,
+            (
+                (handle_player_shoot, handle_player_movement).in_set(GameplaySet::Input),
+                spawn_bullets.in_set(GameplaySet::Spawn),
                 (
-                    update_player_state,
-                    handle_player_shoot,
-                    spawn_bullets,
-                    apply_bullet_movement,
-                    update_animations::<Bullet>,
-                    update_animation_stack,
-                    update_player_animation,
                     (
-                        handle_player_movement,
                         apply_player_movement,
+                        apply_knockback,
                         confine_player_movement,
+                        update_player_state,
                     )
                         .chain(),
-                    check_player_health,
+                    apply_bullet_movement,
+                )
+                    .in_set(GameplaySet::Move),
+                (
+                    check_player_health.after(apply_player_damage),
+                    update_invincibility,
                 )
-                    .run_if(in_state(AppState::Game).and(in_state(GameState::Playing))),
+                    .in_set(GameplaySet::Resolve),
             )
-            .add_systems(Update, remove_out_of_bound_bullets);
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            FixedUpdate,
+            remove_out_of_bound_bullets
+                .after(apply_bullet_movement)
+                .in_set(GameplaySet::Move),
+        )
+        .add_systems(
+            Update,
+            (
+                update_animations::<Bullet>,
+                update_animation_stack,
+                update_player_animation,
+                blink_invincible_player,
+            )
+                .run_if(in_state(GameState::Playing)),
+        );
     }
 }
 
@@ -78,6 +116,14 @@ impl Plugin for PlayerPlugin {
 #[derive(Component)]
 pub struct Player;
 
+/// The player was hurt and can't be hurt again until the timer runs out
+#[derive(Component)]
+pub struct Invincible(pub Timer);
+
+/// Velocity the player was pushed away with by a hit, slowing down over time
+#[derive(Component)]
+struct Knockback(Vec2);
+
 #[derive(Component, Clone, Default, Debug)]
 pub enum PlayerState {
     #[default]
@@ -94,44 +140,31 @@ struct AnimationStack {
     cycles: u8,
 }
 
-fn spawn_player(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
-) {
-    let texture =
-        asset_server.load_with_settings(SPRITE_SHEET_PATH, |settings: &mut ImageLoaderSettings| {
-            settings.sampler = ImageSampler::nearest();
-        });
-    let layout =
-        TextureAtlasLayout::from_grid(SPRITE_SIZE, SPRITE_COLUMNS, SPRITE_ROWS, None, None);
-    let texture_atlas_layout = texture_atlas_layouts.add(layout);
-
-    let size = SPRITE_SIZE.as_vec2();
+fn spawn_player(mut commands: Commands, sprite_assets: Res<SpriteAssets>, balance: Res<Balance>) {
+    let size = sprite_assets.ship.frame_size.as_vec2();
+    let translation = Vec3::new(0.0, 0.0, 1.0); // keep above bullet entities
 
     commands.spawn((
-        Player,
-        PlayerStats::default(),
+        (Player, StateScoped(AppState::Game)),
+        (
+            PlayerStats::default(),
+            ActiveBuffs::default(),
+            WeaponLevel::default(),
+        ),
         MovementInput {
             direction: Vec2::ZERO,
         },
-        MovementSpeed(PLAYER_SPEED),
+        // TODO: start very slow and gain speed with leveling up
+        MovementSpeed(balance.player_speed),
         Bounds { size: size * 1.8 },
-        Health(10),
+        (Health(MAX_HEALTH), MaxHealth(MAX_HEALTH)),
         PlayerState::default(),
         PrevPlayerState::default(),
         Collider,
-        Shoot::new(PLAYER_SHOOT_COOLDOWN),
-        Transform::from_translation(Vec3::new(0.0, 0.0, 1.0)), // keep above bullet entities
-        Sprite {
-            image: texture,
-            texture_atlas: Some(TextureAtlas {
-                layout: texture_atlas_layout,
-                index: 0,
-            }),
-            custom_size: Some(size * 2.0),
-            ..default()
-        },
+        Shoot::new(balance.player_shoot_cooldown),
+        Transform::from_translation(translation),
+        InterpolatedTranslation::new(translation),
+        sprite_assets.ship.sprite(0, 2.0),
         AnimationConfig::new(IDLE_SPRITES.0, IDLE_SPRITES.1, SPRITE_FPS),
         AnimationStack {
             frames: vec![IDLE_SPRITES],
@@ -141,11 +174,11 @@ fn spawn_player(
 }
 
 fn handle_player_movement(
-    keyboard: Res<ButtonInput<KeyCode>>,
+    player_input: Res<PlayerInput>,
     mut query: Query<&mut MovementInput, With<Player>>,
 ) {
     let mut input = query.single_mut();
-    input.direction = get_input_direction(&keyboard);
+    input.direction = player_input.direction;
 }
 
 fn apply_player_movement(
@@ -162,20 +195,25 @@ fn apply_player_movement(
 }
 
 fn confine_player_movement(
-    window: Query<&Window>,
+    play_area: Res<PlayArea>,
     mut query: Query<(&Bounds, &mut Transform), With<Player>>,
 ) {
-    let window = window.single();
     let (bounds, mut transform) = query.single_mut();
 
-    let half_size = bounds.size / 2.0;
-    let min_x = -window.width() / 2.0 + half_size.x;
-    let max_x = window.width() / 2.0 - half_size.x;
-    let min_y = -window.height() / 2.0 + half_size.y;
-    let max_y = window.height() / 2.0 - half_size.y;
+    let area = confined_area(&play_area, bounds);
+    transform.translation.x = transform.translation.x.clamp(area.min.x, area.max.x);
+    transform.translation.y = transform.translation.y.clamp(area.min.y, area.max.y);
+}
 
-    transform.translation.x = transform.translation.x.clamp(min_x, max_x);
-    transform.translation.y = transform.translation.y.clamp(min_y, max_y);
+/// Where the center of a player with `bounds` can go while staying fully
+/// inside the play area
+pub fn confined_area(play_area: &PlayArea, bounds: &Bounds) -> Rect {
+    let half_size = bounds.size / 2.0;
+    let half_area = play_area.half_size();
+    Rect {
+        min: -half_area + half_size,
+        max: half_area - half_size,
+    }
 }
 
 fn update_player_state(
@@ -269,98 +307,60 @@ fn update_player_animation(
     }
 }
 
-fn get_input_direction(keyboard: &ButtonInput<KeyCode>) -> Vec2 {
-    let mut direction = Vec2::ZERO;
-
-    if keyboard.pressed(KeyCode::ArrowLeft) || keyboard.pressed(KeyCode::KeyA) {
-        direction.x -= 1.0;
-    }
-    if keyboard.pressed(KeyCode::ArrowRight) || keyboard.pressed(KeyCode::KeyD) {
-        direction.x += 1.0;
-    }
-    if keyboard.pressed(KeyCode::ArrowUp) || keyboard.pressed(KeyCode::KeyW) {
-        direction.y += 1.0;
-    }
-    if keyboard.pressed(KeyCode::ArrowDown) || keyboard.pressed(KeyCode::KeyS) {
-        direction.y -= 1.0;
-    }
-
-    // Normalize the direction to prevent faster diagonal movement
-    if direction != Vec2::ZERO {
-        direction = direction.normalize();
-    }
-
-    direction
-}
-
-fn handle_player_shoot(
-    keyboard: Res<ButtonInput<KeyCode>>,
-    mut query: Query<&mut Shoot, With<Player>>,
-) {
+fn handle_player_shoot(player_input: Res<PlayerInput>, mut query: Query<&mut Shoot, With<Player>>) {
     let mut shoot = query.single_mut();
-    shoot.is_shooting = keyboard.pressed(KeyCode::Space);
+    shoot.is_shooting = player_input.shoot;
 }
 
 fn spawn_bullets(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
-    mut query: Query<(&mut Shoot, &PlayerStats, &Transform), With<Player>>,
+    sprite_assets: Res<SpriteAssets>,
+    mut query: Query<(&mut Shoot, &PlayerStats, &WeaponLevel, &Transform), With<Player>>,
     time: Res<Time>,
-    game_sounds: Res<GameSounds>,
+    sounds: Option<Res<SoundAssets>>,
     settings: Res<Settings>,
+    balance: Res<Balance>,
 ) {
-    let (mut shoot, player_stats, transform) = query.single_mut();
+    let (mut shoot, player_stats, weapon_level, transform) = query.single_mut();
     shoot.timer.tick(time.delta());
     if !shoot.is_shooting {
         return;
     }
 
-    let texture = asset_server.load_with_settings(
-        BULLET_SPRITE_PATH,
-        |settings: &mut ImageLoaderSettings| {
-            settings.sampler = ImageSampler::nearest();
-        },
-    );
-    let layout = TextureAtlasLayout::from_grid(
-        BULLET_SPRITE_SIZE,
-        BULLET_SPRITE_COLUMNS,
-        BULLET_SPRITE_ROWS,
-        None,
-        None,
-    );
-    let texture_atlas_layout = texture_atlas_layouts.add(layout);
-
-    let size = BULLET_SPRITE_SIZE.as_vec2();
     if shoot.timer.finished() {
-        commands.spawn((
-            Bullet,
-            // Play shoot sound
-            AudioPlayer::new(game_sounds.shoot.clone()),
-            PlaybackSettings {
-                volume: Volume::new(settings.effect_volume),
-                ..default()
-            },
-            Sprite {
-                image: texture,
-                texture_atlas: Some(TextureAtlas {
-                    layout: texture_atlas_layout,
-                    index: 2,
-                }),
-                custom_size: Some(size * 2.0),
-                ..default()
-            },
-            AnimationConfig::new(2, 3, SPRITE_FPS),
-            Transform::from_translation(transform.translation),
-        ));
+        let pattern = SHOT_PATTERNS[usize::from(weapon_level.0.clamp(1, WeaponLevel::MAX)) - 1];
+        for (index, (offset, angle)) in pattern.iter().enumerate() {
+            let rotation = Quat::from_rotation_z(-angle.to_radians());
+            let translation = transform.translation + Vec3::new(*offset, 0.0, 0.0);
+            let mut bullet = commands.spawn((
+                Bullet {
+                    velocity: (rotation * Vec3::Y).truncate() * balance.bullet_speed,
+                },
+                StateScoped(AppState::Game),
+                sprite_assets.bullet.sprite(2, 2.0),
+                AnimationConfig::new(2, 3, SPRITE_FPS),
+                Transform::from_translation(translation).with_rotation(rotation),
+                InterpolatedTranslation::new(translation),
+            ));
+            // Play the shoot sound once per volley
+            if let (0, Some(sounds)) = (index, &sounds) {
+                bullet.insert((
+                    AudioPlayer::new(sounds.shoot.clone()),
+                    PlaybackSettings {
+                        volume: Volume::new(settings.effect_volume),
+                        ..default()
+                    },
+                ));
+            }
+        }
         let adjusted_cooldown = shoot.get_adjusted_cooldown(player_stats.fire_rate);
         shoot.timer = Shoot::timer_from_cooldown(adjusted_cooldown);
     }
 }
 
-fn apply_bullet_movement(mut query: Populated<&mut Transform, With<Bullet>>, time: Res<Time>) {
-    for mut transform in query.iter_mut() {
-        let movement = Vec2::new(0.0, BULLET_SPEED) * time.delta_secs();
+fn apply_bullet_movement(mut query: Populated<(&Bullet, &mut Transform)>, time: Res<Time>) {
+    for (bullet, mut transform) in query.iter_mut() {
+        let movement = bullet.velocity * time.delta_secs();
         transform.translation += movement.extend(0.0);
     }
 }
@@ -368,15 +368,89 @@ fn apply_bullet_movement(mut query: Populated<&mut Transform, With<Bullet>>, tim
 fn remove_out_of_bound_bullets(
     mut commands: Commands,
     query: Query<(Entity, &Transform), With<Bullet>>,
-    window: Single<&Window>,
+    play_area: Res<PlayArea>,
 ) {
+    let half_area = play_area.half_size();
     for (entity, transform) in &query {
-        if transform.translation.y > window.height() / 2.0 {
+        let position = transform.translation.truncate();
+        // Side bolts can leave through the sides, but none come back down
+        if position.y > half_area.y || position.x.abs() > half_area.x {
             commands.entity(entity).despawn();
         }
     }
 }
 
+/// Take the damage of the first hit while the player can be hurt, and
+/// ignore the rest until the invincibility wears off
+pub fn apply_player_damage(
+    mut commands: Commands,
+    player: Single<(Entity, &mut Health, &mut WeaponLevel, Has<Invincible>), With<Player>>,
+    mut damage_events: EventReader<PlayerDamagedEvent>,
+    mut hurt_events: EventWriter<PlayerHurtEvent>,
+    balance: Res<Balance>,
+) {
+    let (entity, mut health, mut weapon_level, mut invincible) = player.into_inner();
+    for event in damage_events.read() {
+        if invincible {
+            continue;
+        }
+        health.0 -= event.amount;
+        weapon_level.downgrade();
+        commands.entity(entity).insert((
+            Invincible(Timer::from_seconds(
+                balance.player_invincibility,
+                TimerMode::Once,
+            )),
+            Knockback(event.direction * balance.player_knockback),
+        ));
+        hurt_events.send(PlayerHurtEvent {
+            amount: event.amount,
+            source: event.source,
+        });
+        invincible = true;
+    }
+}
+
+fn update_invincibility(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Invincible)>,
+) {
+    for (entity, mut invincible) in &mut query {
+        if invincible.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Invincible>();
+        }
+    }
+}
+
+fn apply_knockback(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Knockback, &mut Transform)>,
+) {
+    let delta = time.delta_secs();
+    for (entity, mut knockback, mut transform) in &mut query {
+        transform.translation += (knockback.0 * delta).extend(0.0);
+        knockback.0 *= (-KNOCKBACK_DECAY * delta).exp();
+        if knockback.0.length() < 1.0 {
+            commands.entity(entity).remove::<Knockback>();
+        }
+    }
+}
+
+/// Flicker the player while it is invincible
+fn blink_invincible_player(mut query: Query<(&mut Visibility, Option<&Invincible>), With<Player>>) {
+    for (mut visibility, invincible) in &mut query {
+        let hidden = invincible.is_some_and(|invincible| {
+            (invincible.0.elapsed_secs() / BLINK_INTERVAL) as u32 % 2 == 1
+        });
+        visibility.set_if_neq(match hidden {
+            true => Visibility::Hidden,
+            false => Visibility::Inherited,
+        });
+    }
+}
+
 fn check_player_health(
     player_health: Single<&Health, With<Player>>,
     mut game_state: ResMut<NextState<GameState>>,
@@ -386,8 +460,3 @@ fn check_player_health(
         game_state.set(GameState::GameOver);
     }
 }
-
-fn cleanup_player(mut commands: Commands, player: Single<Entity, With<Player>>) {
-    let entity = player.into_inner();
-    commands.entity(entity).despawn();
-}
@@ -0,0 +1,319 @@
+use bevy::{ecs::system::SystemParam, input::touch::TouchInput, prelude::*, window::PrimaryWindow};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    components::Bounds,
+    input::{ActionSystem, PlayerInput},
+    loading::UiAssets,
+    play_area::{PlayArea, PlayAreaCamera},
+    player::{confined_area, Player},
+    settings::Settings,
+    theme::Palette,
+    AppState,
+};
+
+/// Distance from the pointer, in world units, at which the ship starts
+/// slowing down to stop on it
+const EASE_DISTANCE: f32 = 64.0;
+
+// Sizes of the touch controls, in pixels of the play area's viewport
+const JOYSTICK_RADIUS: f32 = 64.0;
+const KNOB_RADIUS: f32 = 24.0;
+const FIRE_BUTTON_RADIUS: f32 = 48.0;
+/// Distance of the touch controls from the bottom corners
+const TOUCH_CONTROLS_MARGIN: f32 = 32.0;
+const TOUCH_CONTROLS_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.2);
+
+/// How the ship is steered, picked on the settings screen
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ControlScheme {
+    /// Keys, gamepads and the touch joystick
+    #[default]
+    Keys,
+    /// The ship follows the mouse or the touch point, firing while the mouse
+    /// button is held or the screen is touched
+    Pointer,
+}
+
+impl ControlScheme {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ControlScheme::Keys => "Keys",
+            ControlScheme::Pointer => "Pointer",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            ControlScheme::Keys => ControlScheme::Pointer,
+            ControlScheme::Pointer => ControlScheme::Keys,
+        }
+    }
+}
+
+/// Steers the ship with the mouse or touches, and shows a virtual joystick
+/// and fire button once the screen has been touched. Needs a window.
+pub struct PointerInputPlugin;
+impl Plugin for PointerInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PreUpdate,
+            (
+                detect_touch_device.run_if(not(resource_exists::<TouchDevice>)),
+                steer_with_pointer,
+            )
+                .chain()
+                .after(ActionSystem),
+        )
+        .add_systems(
+            Update,
+            (spawn_touch_controls, update_joystick_knob)
+                .chain()
+                .run_if(in_state(AppState::Game).and(resource_exists::<TouchDevice>)),
+        );
+    }
+}
+
+/// The screen has been touched, so there may be no keyboard
+#[derive(Resource, Default)]
+struct TouchDevice;
+
+#[derive(Component)]
+struct TouchControls;
+
+#[derive(Component)]
+struct JoystickKnob;
+
+/// The touch control a touch started on
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TouchControl {
+    Joystick,
+    FireButton,
+}
+
+impl TouchControl {
+    /// The control at `position` in a viewport of `size`, with a little
+    /// leeway for fingers
+    pub fn at(position: Vec2, size: Vec2) -> Option<TouchControl> {
+        if position.distance(joystick_center(size)) <= JOYSTICK_RADIUS * 1.5 {
+            Some(TouchControl::Joystick)
+        } else if position.distance(fire_button_center(size)) <= FIRE_BUTTON_RADIUS * 1.25 {
+            Some(TouchControl::FireButton)
+        } else {
+            None
+        }
+    }
+}
+
+fn joystick_center(size: Vec2) -> Vec2 {
+    Vec2::new(
+        TOUCH_CONTROLS_MARGIN + JOYSTICK_RADIUS,
+        size.y - TOUCH_CONTROLS_MARGIN - JOYSTICK_RADIUS,
+    )
+}
+
+fn fire_button_center(size: Vec2) -> Vec2 {
+    size - Vec2::splat(TOUCH_CONTROLS_MARGIN + FIRE_BUTTON_RADIUS)
+}
+
+/// Direction of a touch at `position` on the joystick, as if it were a
+/// gamepad stick
+pub fn joystick_direction(position: Vec2, size: Vec2) -> Vec2 {
+    let offset = (position - joystick_center(size)) / JOYSTICK_RADIUS;
+    // Viewport y points down, world y up
+    Vec2::new(offset.x, -offset.y).clamp_length_max(1.0)
+}
+
+/// Direction that takes a ship at `position` to `target`, slowing down over
+/// the last [`EASE_DISTANCE`] so it stops on it. Targets outside `area`, where
+/// the ship can't go, are moved to its edge.
+pub fn steer_towards(position: Vec2, target: Vec2, area: Rect) -> Vec2 {
+    let target = target.clamp(area.min, area.max);
+    ((target - position) / EASE_DISTANCE).clamp_length_max(1.0)
+}
+
+fn detect_touch_device(mut commands: Commands, mut touch_events: EventReader<TouchInput>) {
+    if touch_events.read().next().is_some() {
+        commands.init_resource::<TouchDevice>();
+    }
+}
+
+/// The mouse and touch screen, as read by [`steer_with_pointer`]
+#[derive(SystemParam)]
+struct Pointers<'w> {
+    mouse: Res<'w, ButtonInput<MouseButton>>,
+    touches: Res<'w, Touches>,
+    touch_device: Option<Res<'w, TouchDevice>>,
+}
+
+/// Replace the sampled keys with the pointer where it is steering
+fn steer_with_pointer(
+    settings: Res<Settings>,
+    pointers: Pointers,
+    play_area: Res<PlayArea>,
+    window: Option<Single<&Window, With<PrimaryWindow>>>,
+    camera: Option<Single<(&Camera, &GlobalTransform), With<PlayAreaCamera>>>,
+    player: Option<Single<(&Transform, &Bounds), With<Player>>>,
+    mut input: ResMut<PlayerInput>,
+) {
+    let (Some(window), Some(camera), Some(player)) = (window, camera, player) else {
+        return;
+    };
+    let (camera, camera_transform) = *camera;
+    let Some(viewport) = camera.logical_viewport_rect() else {
+        return;
+    };
+    let pointer_scheme = settings.control_scheme == ControlScheme::Pointer;
+    let Pointers {
+        mouse,
+        touches,
+        touch_device,
+    } = pointers;
+
+    let mut stick = None;
+    let mut target = None;
+    let mut fire = false;
+    for touch in touches.iter() {
+        let start = touch.start_position() - viewport.min;
+        let control = touch_device
+            .is_some()
+            .then(|| TouchControl::at(start, viewport.size()))
+            .flatten();
+        match control {
+            Some(TouchControl::Joystick) => {
+                stick = Some(joystick_direction(
+                    touch.position() - viewport.min,
+                    viewport.size(),
+                ));
+            }
+            Some(TouchControl::FireButton) => fire = true,
+            None if pointer_scheme => {
+                target = Some(touch.position() - viewport.min);
+                fire = true;
+            }
+            None => (),
+        }
+    }
+
+    // Touches also move the cursor in some browsers, so a touch device only
+    // follows touches
+    if pointer_scheme && touch_device.is_none() {
+        target = window.cursor_position().map(|cursor| cursor - viewport.min);
+        fire = mouse.pressed(MouseButton::Left);
+    }
+
+    if let Some(stick) = stick {
+        input.direction = stick;
+    } else if let Some(target) = target {
+        if let Ok(target) = camera.viewport_to_world_2d(camera_transform, target) {
+            let (transform, bounds) = *player;
+            input.direction = steer_towards(
+                transform.translation.truncate(),
+                target,
+                confined_area(&play_area, bounds),
+            );
+        }
+    }
+    input.shoot |= fire;
+}
+
+fn spawn_touch_controls(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    camera: Single<Entity, With<PlayAreaCamera>>,
+    controls: Query<(), With<TouchControls>>,
+) {
+    if !controls.is_empty() {
+        return;
+    }
+
+    let circle = |radius: f32| Node {
+        position_type: PositionType::Absolute,
+        width: Val::Px(radius * 2.0),
+        height: Val::Px(radius * 2.0),
+        align_items: AlignItems::Center,
+        justify_content: JustifyContent::Center,
+        ..default()
+    };
+
+    // Laid out in the play area's viewport, the same space touches are
+    // hit-tested in, so the controls line up with it when letterboxed
+    let root = commands
+        .spawn((
+            TouchControls,
+            StateScoped(AppState::Game),
+            TargetCamera(*camera),
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            PickingBehavior::IGNORE,
+        ))
+        .id();
+
+    commands
+        .spawn((
+            Node {
+                left: Val::Px(TOUCH_CONTROLS_MARGIN),
+                bottom: Val::Px(TOUCH_CONTROLS_MARGIN),
+                ..circle(JOYSTICK_RADIUS)
+            },
+            BackgroundColor(TOUCH_CONTROLS_COLOR),
+            BorderRadius::MAX,
+        ))
+        .set_parent(root)
+        .with_child((
+            JoystickKnob,
+            circle(KNOB_RADIUS),
+            BackgroundColor(TOUCH_CONTROLS_COLOR),
+            BorderRadius::MAX,
+        ));
+
+    commands
+        .spawn((
+            Node {
+                right: Val::Px(TOUCH_CONTROLS_MARGIN),
+                bottom: Val::Px(TOUCH_CONTROLS_MARGIN),
+                ..circle(FIRE_BUTTON_RADIUS)
+            },
+            BackgroundColor(TOUCH_CONTROLS_COLOR),
+            BorderRadius::MAX,
+        ))
+        .set_parent(root)
+        .with_child((
+            Text::new("Fire"),
+            TextFont {
+                font: ui_assets.font.clone(),
+                font_size: 24.0,
+                ..default()
+            },
+            TextColor(Palette::TEXT_PRIMARY),
+        ));
+}
+
+/// Show where the joystick is being pushed
+fn update_joystick_knob(
+    touches: Res<Touches>,
+    camera: Option<Single<&Camera, With<PlayAreaCamera>>>,
+    mut knob: Single<&mut Node, With<JoystickKnob>>,
+) {
+    let Some(viewport) = camera.and_then(|camera| camera.logical_viewport_rect()) else {
+        return;
+    };
+
+    let direction = touches
+        .iter()
+        .filter(|touch| {
+            TouchControl::at(touch.start_position() - viewport.min, viewport.size())
+                == Some(TouchControl::Joystick)
+        })
+        .map(|touch| joystick_direction(touch.position() - viewport.min, viewport.size()))
+        .next()
+        .unwrap_or(Vec2::ZERO);
+
+    let offset = Vec2::new(direction.x, -direction.y) * JOYSTICK_RADIUS;
+    knob.left = Val::Px(JOYSTICK_RADIUS - KNOB_RADIUS + offset.x);
+    knob.top = Val::Px(JOYSTICK_RADIUS - KNOB_RADIUS + offset.y);
+}
@@ -1,60 +1,66 @@
-use bevy::{
-    image::{ImageLoaderSettings, ImageSampler},
-    prelude::*,
-};
+use bevy::{ecs::system::SystemParam, prelude::*};
+use rand::prelude::*;
+use serde::Deserialize;
 
 use crate::{
-    collisions::Collider,
-    components::{Bounds, MovementSpeed, PlayerStats},
-    enemies::{EnemyDestroyedEvent, EnemyType},
-    game::GameRestartEvent,
+    balance::Balance,
+    buffs::{update_buffs, ActiveBuffs, BuffKind},
+    collisions::{check_enemy_projectile_player_collision, Collider},
+    components::{Bounds, Health, MaxHealth, MovementSpeed, WeaponLevel},
+    enemies::EnemyDestroyedEvent,
+    enemy_defs::EnemyDefs,
+    game::{despawn_all, GameplaySet, Score, StartRun, StartRunSet},
     game_state::GameState,
+    interpolation::InterpolatedTranslation,
+    play_area::PlayArea,
     player::Player,
+    rng::PowerupDropRng,
     sprite_animation::{update_animations, AnimationConfig},
+    sprite_assets::SpriteAssets,
     AppState,
 };
 
-const MAX_POWERUPS: usize = 3;
-
 struct PowerupsConfig {
-    sprite_path: &'static str,
-    sprite_size: UVec2,
-    sprite_columns: u32,
-    sprite_rows: u32,
     sprite_fps: u8,
     speed: f32,
     scale: f32,
     //spawn_weight: f32,
 }
 
-#[derive(Component, Clone, Copy)]
+#[derive(Component, Deserialize, Clone, Copy, Debug)]
 pub enum PowerupType {
     FireRate,
     Speed,
+    WeaponUpgrade,
+    HealthBoost,
 }
 impl PowerupType {
     fn config(&self) -> PowerupsConfig {
         match self {
             PowerupType::FireRate => PowerupsConfig {
-                sprite_path: "powerup.png",
-                sprite_size: UVec2::new(16, 16),
-                sprite_columns: 2,
-                sprite_rows: 2,
                 sprite_fps: 12,
                 speed: 50.0,
                 scale: 2.0,
                 //spawn_weight: 1.0,
             },
             PowerupType::Speed => PowerupsConfig {
-                sprite_path: "powerup.png",
-                sprite_size: UVec2::new(16, 16),
-                sprite_columns: 2,
-                sprite_rows: 2,
                 sprite_fps: 12,
                 speed: 50.0,
                 scale: 2.0,
                 //spawn_weight: 1.0,
             },
+            PowerupType::WeaponUpgrade => PowerupsConfig {
+                sprite_fps: 12,
+                speed: 40.0,
+                scale: 2.0,
+                //spawn_weight: 1.0,
+            },
+            PowerupType::HealthBoost => PowerupsConfig {
+                sprite_fps: 8,
+                speed: 40.0,
+                scale: 2.0,
+                //spawn_weight: 1.0,
+            },
         }
     }
 }
@@ -72,99 +78,135 @@ pub struct PowerupsPlugin;
 impl Plugin for PowerupsPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<PowerupCount>()
-            .add_systems(OnEnter(AppState::Game), powerups_setup)
             .add_systems(
-                Update,
+                StartRun,
+                (
+                    despawn_all::<Powerup>.in_set(StartRunSet::Despawn),
+                    reset_powerup_count.in_set(StartRunSet::Reset),
+                ),
+            )
+            .add_systems(OnExit(AppState::Game), reset_powerup_count)
+            .add_systems(
+                FixedUpdate,
                 (
-                    spawn_powerups,
-                    apply_powerup_movement,
-                    remove_fallen_powerups,
-                    handle_powerup_collisions,
-                    update_animations::<Powerup>,
+                    spawn_powerups.in_set(GameplaySet::Spawn),
+                    (apply_powerup_movement, remove_fallen_powerups)
+                        .chain()
+                        .in_set(GameplaySet::Move),
+                    handle_powerup_collisions
+                        .after(check_enemy_projectile_player_collision)
+                        .in_set(GameplaySet::Collide),
+                    update_buffs.in_set(GameplaySet::Resolve),
                 )
-                    .run_if(in_state(AppState::Game).and(in_state(GameState::Playing))),
+                    .run_if(in_state(GameState::Playing)),
             )
-            .add_systems(Update, reset_powerups);
+            .add_systems(
+                Update,
+                update_animations::<Powerup>.run_if(in_state(GameState::Playing)),
+            );
     }
 }
 
-fn powerups_setup(mut powerup_count: ResMut<PowerupCount>) {
-    // Reset all counts to 0 when entering Playing state
+fn reset_powerup_count(mut powerup_count: ResMut<PowerupCount>) {
+    // Reset all counts to 0 when a game starts or ends
     *powerup_count = PowerupCount::default();
 }
 
+/// What dropping a powerup needs
+#[derive(SystemParam)]
+struct PowerupSpawner<'w, 's> {
+    commands: Commands<'w, 's>,
+    sprite_assets: Res<'w, SpriteAssets>,
+    powerup_count: ResMut<'w, PowerupCount>,
+}
+
+impl PowerupSpawner<'_, '_> {
+    fn spawn(&mut self, powerup_type: PowerupType, position: Vec3) {
+        spawn_powerup(
+            &mut self.commands,
+            &self.sprite_assets,
+            &mut self.powerup_count,
+            powerup_type,
+            position,
+        );
+    }
+}
+
 fn spawn_powerups(
-    mut commands: Commands,
-    mut powerup_count: ResMut<PowerupCount>,
-    asset_server: Res<AssetServer>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut spawner: PowerupSpawner,
+    mut rng: ResMut<PowerupDropRng>,
+    enemy_defs: Res<EnemyDefs>,
     mut enemy_destroyed_event: EventReader<EnemyDestroyedEvent>,
+    player: Query<(&Health, &MaxHealth), With<Player>>,
+    balance: Res<Balance>,
 ) {
     // Only spawn new powerups if we haven't reached the maximum
-    if powerup_count.0 >= MAX_POWERUPS {
+    if spawner.powerup_count.0 >= balance.max_powerups {
         return;
     }
 
+    let low_health = player
+        .get_single()
+        .is_ok_and(|(health, max_health)| health.0 * 2 <= max_health.0);
+
     for event in enemy_destroyed_event.read() {
-        let enemy_type = event.0.enemy_type;
-        if let EnemyType::Small = enemy_type {
+        // A player low on health gets a first roll for a health powerup
+        if low_health && rng.gen_bool(balance.low_health_drop_chance) {
+            spawner.spawn(PowerupType::HealthBoost, event.0.position);
             continue;
-        };
+        }
 
-        let powerup_type = match enemy_type {
-            EnemyType::Medium => PowerupType::Speed,
-            EnemyType::Large => PowerupType::FireRate,
-            _ => PowerupType::Speed,
+        // Enemies drop the first powerup of their drop table that comes up
+        let Some(drop) = enemy_defs
+            .get(event.0.enemy_type)
+            .drops
+            .iter()
+            .find(|drop| rng.gen_bool(drop.chance))
+        else {
+            continue;
         };
 
-        let config = powerup_type.config();
-        let size = config.sprite_size.as_vec2();
-        let spawn_position = event.0.position;
+        spawner.spawn(drop.powerup, event.0.position);
+    }
+}
 
-        let texture = asset_server.load_with_settings(
-            config.sprite_path,
-            |settings: &mut ImageLoaderSettings| {
-                settings.sampler = ImageSampler::nearest();
-            },
-        );
-        let layout = TextureAtlasLayout::from_grid(
-            config.sprite_size,
-            config.sprite_columns,
-            config.sprite_rows,
-            None,
-            None,
-        );
-        let texture_atlas_layout = texture_atlas_layouts.add(layout);
+/// Spawn a powerup of the given type centered on `position`
+pub fn spawn_powerup(
+    commands: &mut Commands,
+    sprite_assets: &SpriteAssets,
+    powerup_count: &mut PowerupCount,
+    powerup_type: PowerupType,
+    position: Vec3,
+) -> Entity {
+    let config = powerup_type.config();
+    // Every powerup is on the same sheet, two frames to a row
+    let sheet = &sprite_assets.powerup;
+    let size = sheet.frame_size.as_vec2();
+    let first_frame = match powerup_type {
+        PowerupType::FireRate => 0,
+        PowerupType::Speed => 2,
+        PowerupType::WeaponUpgrade => 4,
+        PowerupType::HealthBoost => 6,
+    };
 
-        let animation_config = match powerup_type {
-            PowerupType::FireRate => AnimationConfig::new(0, 1, config.sprite_fps),
-            PowerupType::Speed => AnimationConfig::new(2, 3, config.sprite_fps),
-        };
-        commands.spawn((
+    let entity = commands
+        .spawn((
             Powerup { powerup_type },
+            StateScoped(AppState::Game),
             Collider,
-            Transform::from_translation(spawn_position),
+            Transform::from_translation(position),
+            InterpolatedTranslation::new(position),
             MovementSpeed(config.speed),
             Bounds {
                 size: size * config.scale,
             },
-            Sprite {
-                image: texture,
-                texture_atlas: Some(TextureAtlas {
-                    layout: texture_atlas_layout,
-                    index: match powerup_type {
-                        PowerupType::FireRate => 0,
-                        PowerupType::Speed => 2,
-                    },
-                }),
-                custom_size: Some(size * config.scale),
-                ..default()
-            },
-            animation_config,
-        ));
+            sheet.sprite(first_frame, config.scale),
+            AnimationConfig::new(first_frame, first_frame + 1, config.sprite_fps),
+        ))
+        .id();
 
-        powerup_count.0 += 1;
-    }
+    powerup_count.0 += 1;
+    entity
 }
 
 fn apply_powerup_movement(
@@ -181,23 +223,42 @@ fn remove_fallen_powerups(
     mut commands: Commands,
     mut powerup_count: ResMut<PowerupCount>,
     query: Query<(Entity, &Transform), With<Powerup>>,
-    window: Single<&Window>,
+    play_area: Res<PlayArea>,
 ) {
     for (entity, transform) in &query {
-        if transform.translation.y < -window.height() / 2.0 {
+        if transform.translation.y < -play_area.height() / 2.0 {
             commands.entity(entity).despawn();
             powerup_count.0 -= 1;
         }
     }
 }
 
+/// Where the player is and everything a powerup can boost
+type PowerupCollector<'a> = (
+    &'a Transform,
+    &'a Bounds,
+    &'a mut Health,
+    &'a MaxHealth,
+    &'a mut ActiveBuffs,
+    &'a mut WeaponLevel,
+);
+
 fn handle_powerup_collisions(
     mut commands: Commands,
     mut powerup_count: ResMut<PowerupCount>,
-    mut player_query: Query<(&Transform, &Bounds, &mut PlayerStats), With<Player>>,
+    player: Single<PowerupCollector, With<Player>>,
     powerup_query: Query<(Entity, &Transform, &Bounds, &Powerup)>,
+    mut score: ResMut<Score>,
+    balance: Res<Balance>,
 ) {
-    let (player_transform, player_bounds, mut player_stats) = player_query.single_mut();
+    let (
+        player_transform,
+        player_bounds,
+        mut player_health,
+        max_health,
+        mut buffs,
+        mut weapon_level,
+    ) = player.into_inner();
 
     for (powerup_entity, powerup_transform, powerup_bounds, powerup) in powerup_query.iter() {
         let player_pos = player_transform.translation.truncate();
@@ -210,17 +271,22 @@ fn handle_powerup_collisions(
                 < (player_bounds.size.y + powerup_bounds.size.y) / 2.0
         {
             match powerup.powerup_type {
-                // PowerupType::HealthBoost => {
-                //     stats.health.0 = (stats.health.0 + 1).min(3); // Max health of 3
-                // },
+                PowerupType::HealthBoost => {
+                    player_health.0 = (player_health.0 + balance.health_powerup).min(max_health.0);
+                }
                 PowerupType::FireRate => {
-                    player_stats.fire_rate *= 1.5;
+                    if !buffs.add(BuffKind::FireRate, &balance.fire_rate_buff) {
+                        **score += balance.maxed_buff_score;
+                    }
                 }
                 PowerupType::Speed => {
-                    player_stats.speed *= 1.2;
-                } // PowerupType::WeaponUpgrade => {
-                  //     stats.weapon_level = (stats.weapon_level + 1).min(3); // Max weapon level of 3
-                  // },
+                    if !buffs.add(BuffKind::Speed, &balance.speed_buff) {
+                        **score += balance.maxed_buff_score;
+                    }
+                }
+                PowerupType::WeaponUpgrade => {
+                    weapon_level.upgrade();
+                }
             }
 
             commands.entity(powerup_entity).despawn();
@@ -228,21 +294,3 @@ fn handle_powerup_collisions(
         }
     }
 }
-
-fn reset_powerups(
-    mut commands: Commands,
-    mut powerup_count: ResMut<PowerupCount>,
-    mut game_restart_event: EventReader<GameRestartEvent>,
-    query: Query<Entity, With<Powerup>>,
-) {
-    if !game_restart_event.is_empty() {
-        game_restart_event.clear();
-
-        for entity in &query {
-            commands.entity(entity).despawn();
-        }
-
-        // Reset all counts to 0
-        *powerup_count = PowerupCount::default();
-    }
-}
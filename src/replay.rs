@@ -0,0 +1,563 @@
+use std::{
+    fmt::{self, Debug},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    balance::Balance,
+    components::Health,
+    enemies::Enemy,
+    enemy_defs::EnemyDefs,
+    game::{Score, StartRun, StartRunSet},
+    game_state::GameState,
+    input::PlayerInput,
+    levels::Levels,
+    player::Player,
+    rng::RunSeed,
+    settings::Settings,
+    AppState,
+};
+
+const MAGIC: &[u8; 4] = b"SSRP";
+const FORMAT_VERSION: u16 = 2;
+pub const GAME_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// A world state checksum is stored once every this many ticks
+const CHECKSUM_INTERVAL: usize = 64;
+
+/// Everything needed to reproduce a run: the conditions it started from and
+/// the input of every gameplay tick.
+///
+/// The binary layout, all little endian:
+///
+/// ```text
+/// "SSRP" | format version: u16 | game version: u8 length + utf8
+/// seed: u64 | music volume: f32 | effect volume: f32 | data hash: u64
+/// input run count: u32 | (tick count: u16, x: f32, y: f32, shoot: u8) per run
+/// checksum count: u32 | (tick: u32, hash: u64) per checksum
+/// ```
+///
+/// Consecutive ticks with identical input are stored as a single run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    pub header: ReplayHeader,
+    pub inputs: Vec<PlayerInput>,
+    pub checksums: Vec<Checksum>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplayHeader {
+    pub game_version: String,
+    pub seed: u64,
+    pub music_volume: f32,
+    pub effect_volume: f32,
+    /// [`gameplay_data_hash`] of the balance, levels and enemies the run was
+    /// played with
+    pub data_hash: u64,
+}
+
+/// Hash of the world state at the end of a tick, counting from 1
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Checksum {
+    pub tick: u32,
+    pub hash: u64,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    NotAReplay,
+    UnsupportedFormat(u16),
+    Truncated,
+    InvalidGameVersion,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Io(error) => write!(f, "could not read replay: {error}"),
+            ReplayError::NotAReplay => write!(f, "not a replay file"),
+            ReplayError::UnsupportedFormat(version) => {
+                write!(f, "unsupported replay format version {version}")
+            }
+            ReplayError::Truncated => write!(f, "replay file is truncated"),
+            ReplayError::InvalidGameVersion => write!(f, "replay has an invalid game version"),
+        }
+    }
+}
+
+impl From<io::Error> for ReplayError {
+    fn from(error: io::Error) -> Self {
+        ReplayError::Io(error)
+    }
+}
+
+impl Replay {
+    pub fn load(path: impl AsRef<Path>) -> Result<Replay, ReplayError> {
+        Replay::decode(&fs::read(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(path, self.encode())
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+
+        let game_version = self.header.game_version.as_bytes();
+        let game_version = &game_version[..game_version.len().min(u8::MAX as usize)];
+        bytes.push(game_version.len() as u8);
+        bytes.extend_from_slice(game_version);
+        bytes.extend_from_slice(&self.header.seed.to_le_bytes());
+        bytes.extend_from_slice(&self.header.music_volume.to_le_bytes());
+        bytes.extend_from_slice(&self.header.effect_volume.to_le_bytes());
+        bytes.extend_from_slice(&self.header.data_hash.to_le_bytes());
+
+        let mut runs: Vec<(u16, PlayerInput)> = Vec::new();
+        for input in &self.inputs {
+            match runs.last_mut() {
+                Some((ticks, last)) if last == input && *ticks < u16::MAX => *ticks += 1,
+                _ => runs.push((1, *input)),
+            }
+        }
+        bytes.extend_from_slice(&(runs.len() as u32).to_le_bytes());
+        for (ticks, input) in runs {
+            bytes.extend_from_slice(&ticks.to_le_bytes());
+            bytes.extend_from_slice(&input.direction.x.to_le_bytes());
+            bytes.extend_from_slice(&input.direction.y.to_le_bytes());
+            bytes.push(input.shoot as u8);
+        }
+
+        bytes.extend_from_slice(&(self.checksums.len() as u32).to_le_bytes());
+        for checksum in &self.checksums {
+            bytes.extend_from_slice(&checksum.tick.to_le_bytes());
+            bytes.extend_from_slice(&checksum.hash.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Replay, ReplayError> {
+        let mut reader = Reader { bytes };
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(ReplayError::NotAReplay);
+        }
+        let format_version = u16::from_le_bytes(reader.array()?);
+        if format_version != FORMAT_VERSION {
+            return Err(ReplayError::UnsupportedFormat(format_version));
+        }
+
+        let game_version_length = reader.take(1)?[0] as usize;
+        let game_version = String::from_utf8(reader.take(game_version_length)?.to_vec())
+            .map_err(|_| ReplayError::InvalidGameVersion)?;
+        let header = ReplayHeader {
+            game_version,
+            seed: u64::from_le_bytes(reader.array()?),
+            music_volume: f32::from_le_bytes(reader.array()?),
+            effect_volume: f32::from_le_bytes(reader.array()?),
+            data_hash: u64::from_le_bytes(reader.array()?),
+        };
+
+        let mut inputs = Vec::new();
+        let run_count = u32::from_le_bytes(reader.array()?);
+        for _ in 0..run_count {
+            let ticks = u16::from_le_bytes(reader.array()?);
+            let input = PlayerInput {
+                direction: Vec2::new(
+                    f32::from_le_bytes(reader.array()?),
+                    f32::from_le_bytes(reader.array()?),
+                ),
+                shoot: reader.take(1)?[0] != 0,
+            };
+            inputs.extend(std::iter::repeat_n(input, ticks as usize));
+        }
+
+        let mut checksums = Vec::new();
+        let checksum_count = u32::from_le_bytes(reader.array()?);
+        for _ in 0..checksum_count {
+            checksums.push(Checksum {
+                tick: u32::from_le_bytes(reader.array()?),
+                hash: u64::from_le_bytes(reader.array()?),
+            });
+        }
+
+        Ok(Replay {
+            header,
+            inputs,
+            checksums,
+        })
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], ReplayError> {
+        if self.bytes.len() < count {
+            return Err(ReplayError::Truncated);
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], ReplayError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+}
+
+pub enum ReplayPlugin {
+    /// Record every run and save it to `directory` when the game is over.
+    /// Does nothing on the web, which has no file system to save to.
+    Record { directory: PathBuf },
+    /// Feed the inputs of `replay` to the game instead of the keyboard
+    Playback {
+        replay: Replay,
+        exit_when_done: bool,
+    },
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        let in_gameplay = in_state(GameState::Playing);
+
+        match self {
+            // `save_replay` needs a file system and a clock, and `SystemTime`
+            // panics on the web
+            ReplayPlugin::Record { .. } if cfg!(target_arch = "wasm32") => {}
+            ReplayPlugin::Record { directory } => {
+                app.insert_resource(ReplayDirectory(directory.clone()))
+                    .init_resource::<ReplayRecorder>()
+                    .add_systems(StartRun, start_recording.in_set(StartRunSet::Reset))
+                    .add_systems(FixedPreUpdate, record_input.run_if(in_gameplay.clone()))
+                    .add_systems(FixedPostUpdate, record_checksum.run_if(in_gameplay))
+                    .add_systems(OnEnter(GameState::GameOver), save_replay)
+                    .add_systems(OnExit(AppState::Game), save_replay)
+                    // Keep runs that are cut short by closing the game, if one
+                    // has started at all
+                    .add_systems(
+                        Last,
+                        save_replay.run_if(on_event::<AppExit>.and(resource_exists::<RunSeed>)),
+                    );
+            }
+            ReplayPlugin::Playback {
+                replay,
+                exit_when_done,
+            } => {
+                if replay.header.game_version != GAME_VERSION {
+                    warn!(
+                        "Replay was recorded with version {} of the game, this is {}",
+                        replay.header.game_version, GAME_VERSION
+                    );
+                }
+
+                app.insert_resource(ReplayPlayback {
+                    replay: replay.clone(),
+                    tick: 0,
+                    diverged_at: None,
+                    finished: false,
+                    exit_when_done: *exit_when_done,
+                })
+                .add_systems(StartRun, check_gameplay_data.in_set(StartRunSet::Reset))
+                .add_systems(FixedPreUpdate, play_back_input.run_if(in_gameplay.clone()))
+                .add_systems(FixedPostUpdate, verify_checksum.run_if(in_gameplay))
+                .add_systems(OnEnter(GameState::GameOver), end_playback_on_game_over);
+            }
+        }
+    }
+}
+
+#[derive(Resource)]
+struct ReplayDirectory(PathBuf);
+
+#[derive(Resource, Default)]
+struct ReplayRecorder {
+    data_hash: u64,
+    inputs: Vec<PlayerInput>,
+    checksums: Vec<Checksum>,
+}
+
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    replay: Replay,
+    tick: usize,
+    /// First tick at which the world no longer matched the recording
+    pub diverged_at: Option<u32>,
+    finished: bool,
+    exit_when_done: bool,
+}
+
+fn start_recording(
+    mut recorder: ResMut<ReplayRecorder>,
+    balance: Res<Balance>,
+    levels: Res<Levels>,
+    enemy_defs: Res<EnemyDefs>,
+) {
+    *recorder = ReplayRecorder {
+        data_hash: gameplay_data_hash(&balance, &levels, &enemy_defs),
+        ..default()
+    };
+}
+
+fn record_input(input: Res<PlayerInput>, mut recorder: ResMut<ReplayRecorder>) {
+    recorder.inputs.push(*input);
+}
+
+fn record_checksum(
+    mut recorder: ResMut<ReplayRecorder>,
+    player: Query<&Transform, With<Player>>,
+    enemies: Query<(&Transform, &Enemy, &Health)>,
+    score: Res<Score>,
+) {
+    let tick = recorder.inputs.len();
+    if tick.is_multiple_of(CHECKSUM_INTERVAL) {
+        let hash = world_checksum(&player, &enemies, &score);
+        recorder.checksums.push(Checksum {
+            tick: tick as u32,
+            hash,
+        });
+    }
+}
+
+fn save_replay(
+    mut recorder: ResMut<ReplayRecorder>,
+    directory: Res<ReplayDirectory>,
+    seed: Res<RunSeed>,
+    settings: Res<Settings>,
+) {
+    if recorder.inputs.is_empty() {
+        return;
+    }
+
+    let replay = Replay {
+        header: ReplayHeader {
+            game_version: GAME_VERSION.to_string(),
+            seed: seed.0,
+            music_volume: settings.music_volume,
+            effect_volume: settings.effect_volume,
+            data_hash: recorder.data_hash,
+        },
+        inputs: std::mem::take(&mut recorder.inputs),
+        checksums: std::mem::take(&mut recorder.checksums),
+    };
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let path = directory
+        .0
+        .join(format!("run-{timestamp}-{}.replay", seed.0));
+    match replay.save(&path) {
+        Ok(()) => info!("Saved replay to {}", path.display()),
+        Err(error) => warn!("Could not save replay to {}: {error}", path.display()),
+    }
+}
+
+/// A replay only plays back the same way with the data it was recorded with,
+/// so say so up front rather than with a divergence later on
+fn check_gameplay_data(
+    mut playback: ResMut<ReplayPlayback>,
+    balance: Res<Balance>,
+    levels: Res<Levels>,
+    enemy_defs: Res<EnemyDefs>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    if gameplay_data_hash(&balance, &levels, &enemy_defs) == playback.replay.header.data_hash {
+        return;
+    }
+
+    let message = "Replay was recorded with a different balance file, levels or enemies";
+    if playback.exit_when_done {
+        error!("{message}, so it can't be checked");
+        playback.finished = true;
+        app_exit_events.send(AppExit::error());
+    } else {
+        warn!("{message}, so it won't play back the same");
+    }
+}
+
+fn play_back_input(mut playback: ResMut<ReplayPlayback>, mut input: ResMut<PlayerInput>) {
+    let tick = playback.tick;
+    *input = playback
+        .replay
+        .inputs
+        .get(tick)
+        .copied()
+        .unwrap_or_default();
+    playback.tick += 1;
+}
+
+fn verify_checksum(
+    mut playback: ResMut<ReplayPlayback>,
+    player: Query<&Transform, With<Player>>,
+    enemies: Query<(&Transform, &Enemy, &Health)>,
+    score: Res<Score>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    let tick = playback.tick as u32;
+    let expected = playback
+        .replay
+        .checksums
+        .iter()
+        .find(|checksum| checksum.tick == tick)
+        .copied();
+
+    if let Some(expected) = expected {
+        let hash = world_checksum(&player, &enemies, &score);
+        if hash != expected.hash && playback.diverged_at.is_none() {
+            error!(
+                "Replay diverged at tick {tick}: expected world checksum {:016x}, got {hash:016x}",
+                expected.hash
+            );
+            playback.diverged_at = Some(tick);
+        }
+    }
+
+    if playback.tick == playback.replay.inputs.len() {
+        finish_playback(&mut playback, &mut app_exit_events);
+    }
+}
+
+fn end_playback_on_game_over(
+    mut playback: ResMut<ReplayPlayback>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    if playback.tick < playback.replay.inputs.len() && playback.diverged_at.is_none() {
+        error!(
+            "Replay diverged at tick {}: the game ended {} ticks early",
+            playback.tick,
+            playback.replay.inputs.len() - playback.tick
+        );
+        playback.diverged_at = Some(playback.tick as u32);
+    }
+    finish_playback(&mut playback, &mut app_exit_events);
+}
+
+fn finish_playback(playback: &mut ReplayPlayback, app_exit_events: &mut EventWriter<AppExit>) {
+    if playback.finished {
+        return;
+    }
+    playback.finished = true;
+
+    match playback.diverged_at {
+        None => info!(
+            "Replay finished after {} ticks without diverging",
+            playback.tick
+        ),
+        Some(diverged_at) => error!("Replay finished, diverged at tick {diverged_at}"),
+    }
+    if playback.exit_when_done {
+        app_exit_events.send(match playback.diverged_at {
+            None => AppExit::Success,
+            Some(_) => AppExit::error(),
+        });
+    }
+}
+
+/// Hash of the parts of the world that tell runs apart: the player position,
+/// the set of enemies and the score
+fn world_checksum(
+    player: &Query<&Transform, With<Player>>,
+    enemies: &Query<(&Transform, &Enemy, &Health)>,
+    score: &Score,
+) -> u64 {
+    let mut hasher = Fnv1a::default();
+
+    for transform in player {
+        hasher.write_f32(transform.translation.x);
+        hasher.write_f32(transform.translation.y);
+    }
+
+    // Sort so the hash doesn't depend on query iteration order
+    let mut enemies: Vec<_> = enemies
+        .iter()
+        .map(|(transform, enemy, health)| {
+            (
+                transform.translation.x.to_bits(),
+                transform.translation.y.to_bits(),
+                enemy.enemy_type.index() as u8,
+                health.0,
+            )
+        })
+        .collect();
+    enemies.sort_unstable();
+    for (x, y, enemy_type, health) in enemies {
+        hasher.write(&x.to_le_bytes());
+        hasher.write(&y.to_le_bytes());
+        hasher.write(&[enemy_type]);
+        hasher.write(&health.to_le_bytes());
+    }
+
+    hasher.write(&(**score as u64).to_le_bytes());
+    hasher.0
+}
+
+/// Hash of the data a run plays out from besides the seed and input: the
+/// balance, the levels and every enemy
+pub fn gameplay_data_hash(balance: &Balance, levels: &Levels, enemy_defs: &EnemyDefs) -> u64 {
+    let mut hasher = Fnv1a::default();
+    hasher.write_debug(balance);
+    // Not the levels' handles, whose ids depend on load order
+    hasher.write_debug(&levels.stage_clear_time);
+    hasher.write_debug(&levels.difficulty_step);
+    hasher.write_debug(&levels.stages);
+    hasher.write_debug(&levels.score_bosses);
+    for (_, def) in enemy_defs.iter() {
+        hasher.write_debug(&(
+            &def.name,
+            def.size(),
+            def.speed,
+            def.health,
+            def.contact_damage,
+            def.score,
+            def.spawn_weight,
+            &def.drops,
+            &def.movement,
+            &def.weapon,
+            &def.boss,
+        ));
+    }
+    hasher.0
+}
+
+/// FNV-1a, used over the std hasher because its output is stable across
+/// Rust versions and platforms
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_f32(&mut self, value: f32) {
+        self.write(&value.to_bits().to_le_bytes());
+    }
+
+    /// Hash `value` as it is written out by `Debug`, for data made of many
+    /// nested types
+    fn write_debug(&mut self, value: &impl Debug) {
+        self.write(format!("{value:?}").as_bytes());
+    }
+}
@@ -0,0 +1,43 @@
+use bevy::prelude::*;
+use bevy_rand::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    game::{StartRun, StartRunSet},
+    settings::Settings,
+};
+
+/// Seed the current run was started from
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RunSeed(pub u64);
+
+/// Random stream used to decide when, what and where enemies spawn
+#[derive(Resource, Deref, DerefMut)]
+pub struct EnemySpawnRng(WyRand);
+
+/// Random stream used to decide which powerups drop
+#[derive(Resource, Deref, DerefMut)]
+pub struct PowerupDropRng(WyRand);
+
+pub struct RngPlugin;
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(StartRun, seed_run.in_set(StartRunSet::Reset));
+    }
+}
+
+/// Seed the global entropy for a new run and derive one stream per subsystem
+/// from it, so that a subsystem drawing more numbers never shifts another's.
+fn seed_run(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut global_rng: ResMut<GlobalEntropy<WyRand>>,
+) {
+    let seed = settings.seed.unwrap_or_else(random);
+    info!("Starting run with seed {seed}");
+
+    global_rng.reseed(seed.to_le_bytes());
+    commands.insert_resource(RunSeed(seed));
+    commands.insert_resource(EnemySpawnRng(WyRand::seed_from_u64(global_rng.next_u64())));
+    commands.insert_resource(PowerupDropRng(WyRand::seed_from_u64(global_rng.next_u64())));
+}
@@ -1,6 +1,11 @@
 use bevy::prelude::*;
 
-use crate::{theme::Palette, AppState};
+use crate::{
+    game::{despawn_all, Score, StartRun, StartRunSet},
+    loading::UiAssets,
+    theme::Palette,
+    AppState,
+};
 
 const SCOREBOARD_FONT_SIZE: f32 = 33.0;
 const SCOREBOARD_TEXT_PADDING: Val = Val::Px(5.0);
@@ -10,24 +15,22 @@ pub struct ScoreboardPlugin;
 
 impl Plugin for ScoreboardPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(Score(0))
-            .add_systems(
-                OnEnter(AppState::Game),
-                ((cleanup_scoreboard, setup).chain(), reset_score),
-            )
-            .add_systems(Update, update_scoreboard.run_if(in_state(AppState::Game)));
+        app.add_systems(
+            StartRun,
+            (
+                despawn_all::<ScoreboardUi>.in_set(StartRunSet::Despawn),
+                setup.in_set(StartRunSet::Spawn),
+            ),
+        )
+        .add_systems(Update, update_scoreboard.run_if(in_state(AppState::Game)));
     }
 }
 
-// This resource tracks the game's score
-#[derive(Resource, Deref, DerefMut)]
-pub struct Score(usize);
-
 #[derive(Component)]
 struct ScoreboardUi;
 
-fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let text_font = asset_server.load("../assets/atari_games.ttf");
+fn setup(mut commands: Commands, ui_assets: Res<UiAssets>) {
+    let text_font = ui_assets.font.clone();
 
     // MenuText {
     //     text: Text::new(text),
@@ -46,6 +49,7 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
             },
             TextColor(Palette::TEXT_PRIMARY),
             ScoreboardUi,
+            StateScoped(AppState::Game),
             Node {
                 position_type: PositionType::Absolute,
                 top: SCOREBOARD_TEXT_PADDING,
@@ -64,16 +68,6 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
         ));
 }
 
-fn reset_score(mut score: ResMut<Score>) {
-    score.0 = 0;
-}
-
-fn cleanup_scoreboard(mut commands: Commands, query: Query<Entity, With<ScoreboardUi>>) {
-    for entity in query.iter() {
-        commands.entity(entity).despawn_recursive();
-    }
-}
-
 fn update_scoreboard(
     score: Res<Score>,
     score_root: Single<Entity, (With<ScoreboardUi>, With<Text>)>,
@@ -1,9 +1,42 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{input::InputBindings, menu::menu::MenuState, pointer::ControlScheme};
+
+/// Bumped whenever a field is added, renamed or changes meaning, so older
+/// files can be migrated in [`SettingsFile::migrate`]
+///
+/// 1. Volumes
+/// 2. Key bindings
+/// 3. Gamepad deadzone
+/// 4. Control scheme
+const SETTINGS_VERSION: u32 = 4;
+
+/// Past this a stick would barely move the player at all
+pub const MAX_GAMEPAD_DEADZONE: f32 = 0.9;
+
+/// Saves [`Settings`] when the settings screen is left and when the app
+/// exits. Load them with [`Settings::load`] before adding this.
+pub struct PersistSettingsPlugin;
+impl Plugin for PersistSettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(MenuState::Settings), save_settings)
+            .add_systems(OnExit(MenuState::Controls), save_settings)
+            .add_systems(Last, save_settings.run_if(on_event::<AppExit>));
+    }
+}
 
 #[derive(Resource)]
 pub struct Settings {
     pub music_volume: f32,
     pub effect_volume: f32,
+    pub bindings: InputBindings,
+    /// How far the left stick has to be pushed, from 0 to 1, before it moves
+    /// the player
+    pub gamepad_deadzone: f32,
+    pub control_scheme: ControlScheme,
+    /// Seed every run starts from. A random seed is picked when unset.
+    pub seed: Option<u64>,
 }
 
 impl Settings {
@@ -11,9 +44,56 @@ impl Settings {
         Settings {
             music_volume: 0.5,
             effect_volume: 0.5,
+            bindings: InputBindings::default(),
+            gamepad_deadzone: 0.15,
+            control_scheme: ControlScheme::default(),
+            seed: None,
+        }
+    }
+
+    /// The settings saved by the last session, or the defaults when there
+    /// are none
+    pub fn load() -> Settings {
+        match storage::read() {
+            Ok(Some(text)) => Settings::from_ron(&text),
+            Ok(None) => Settings::new(),
+            Err(error) => {
+                warn!("Could not read the settings, using the defaults: {error}");
+                Settings::new()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        if let Err(error) = storage::write(&self.to_ron()) {
+            warn!("Could not save the settings: {error}");
+        }
+    }
+
+    /// Settings from a saved file, or the defaults if it can't be read
+    pub fn from_ron(text: &str) -> Settings {
+        match ron::from_str::<SettingsFile>(text) {
+            Ok(file) => file.migrate().into_settings(),
+            Err(error) => {
+                warn!("The saved settings are corrupt, using the defaults: {error}");
+                Settings::new()
+            }
         }
     }
 
+    pub fn to_ron(&self) -> String {
+        let file = SettingsFile {
+            version: SETTINGS_VERSION,
+            music_volume: self.music_volume,
+            effect_volume: self.effect_volume,
+            bindings: self.bindings.clone(),
+            gamepad_deadzone: self.gamepad_deadzone,
+            control_scheme: self.control_scheme,
+        };
+        ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default())
+            .expect("settings should always serialize")
+    }
+
     pub fn set_music_volume(&mut self, volume: f32) {
         self.music_volume = volume.clamp(0.0, 1.0);
     }
@@ -21,4 +101,140 @@ impl Settings {
     pub fn set_effect_volume(&mut self, volume: f32) {
         self.effect_volume = volume.clamp(0.0, 1.0);
     }
+
+    pub fn set_gamepad_deadzone(&mut self, deadzone: f32) {
+        self.gamepad_deadzone = deadzone.clamp(0.0, MAX_GAMEPAD_DEADZONE);
+    }
+}
+
+/// The settings as saved. The seed is left out: it is set for one session
+/// from the command line, a replay or the new game screen.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+struct SettingsFile {
+    version: u32,
+    music_volume: f32,
+    effect_volume: f32,
+    bindings: InputBindings,
+    gamepad_deadzone: f32,
+    control_scheme: ControlScheme,
+}
+
+impl Default for SettingsFile {
+    fn default() -> Self {
+        let settings = Settings::new();
+        Self {
+            version: SETTINGS_VERSION,
+            music_volume: settings.music_volume,
+            effect_volume: settings.effect_volume,
+            bindings: settings.bindings,
+            gamepad_deadzone: settings.gamepad_deadzone,
+            control_scheme: settings.control_scheme,
+        }
+    }
+}
+
+impl SettingsFile {
+    /// Bring a file saved by an older version of the game up to date. Fields
+    /// it didn't have yet already got their defaults, e.g. the bindings of a
+    /// version 1 file.
+    fn migrate(mut self) -> Self {
+        if self.version > SETTINGS_VERSION {
+            warn!(
+                "The settings were saved by a newer version of the game, \
+                 keeping what this one understands"
+            );
+        }
+        self.version = SETTINGS_VERSION;
+        self
+    }
+
+    fn into_settings(self) -> Settings {
+        let mut settings = Settings::new();
+        settings.set_music_volume(self.music_volume);
+        settings.set_effect_volume(self.effect_volume);
+        settings.bindings = self.bindings;
+        settings.bindings.add_missing_actions();
+        settings.set_gamepad_deadzone(self.gamepad_deadzone);
+        settings.control_scheme = self.control_scheme;
+        settings
+    }
+}
+
+fn save_settings(settings: Res<Settings>) {
+    settings.save();
+}
+
+/// Saves to `rust-space-shooter/settings.ron` in the platform's config folder
+#[cfg(not(target_arch = "wasm32"))]
+mod storage {
+    use std::{io, path::PathBuf};
+
+    pub fn read() -> io::Result<Option<String>> {
+        let Some(path) = path() else {
+            return Ok(None);
+        };
+        match std::fs::read_to_string(path) {
+            Ok(text) => Ok(Some(text)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn write(text: &str) -> io::Result<()> {
+        let path = path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "there is no config folder"))?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, text)
+    }
+
+    fn path() -> Option<PathBuf> {
+        Some(
+            config_dir()?
+                .join("rust-space-shooter")
+                .join("settings.ron"),
+        )
+    }
+
+    fn config_dir() -> Option<PathBuf> {
+        let var = |name| std::env::var_os(name).filter(|value| !value.is_empty());
+
+        if cfg!(windows) {
+            var("APPDATA").map(PathBuf::from)
+        } else if cfg!(target_os = "macos") {
+            var("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+        } else {
+            var("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .or_else(|| var("HOME").map(|home| PathBuf::from(home).join(".config")))
+        }
+    }
+}
+
+/// Saves to the browser's local storage
+#[cfg(target_arch = "wasm32")]
+mod storage {
+    const KEY: &str = "rust-space-shooter.settings";
+
+    pub fn read() -> Result<Option<String>, String> {
+        local_storage()?
+            .get_item(KEY)
+            .map_err(|error| format!("{error:?}"))
+    }
+
+    pub fn write(text: &str) -> Result<(), String> {
+        local_storage()?
+            .set_item(KEY, text)
+            .map_err(|error| format!("{error:?}"))
+    }
+
+    fn local_storage() -> Result<web_sys::Storage, String> {
+        web_sys::window()
+            .ok_or("there is no browser window")?
+            .local_storage()
+            .map_err(|error| format!("{error:?}"))?
+            .ok_or_else(|| "local storage is turned off".to_string())
+    }
 }
@@ -0,0 +1,121 @@
+use bevy::{
+    asset::{UntypedAssetId, VisitAssetDependencies},
+    image::{ImageLoaderSettings, ImageSampler},
+    prelude::*,
+};
+
+use crate::{loading::AssetManifest, AppState};
+
+/// Loads every sprite sheet and its atlas layout once, when the app is built.
+/// Paths are relative to the `assets` folder.
+pub struct SpriteAssetsPlugin;
+impl Plugin for SpriteAssetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpriteAssets>()
+            .add_systems(OnEnter(AppState::Loading), add_sprites_to_manifest);
+    }
+}
+
+/// An image split into equally sized frames
+#[derive(Clone)]
+pub struct SpriteSheet {
+    pub image: Handle<Image>,
+    pub layout: Handle<TextureAtlasLayout>,
+    pub frame_size: UVec2,
+}
+
+impl SpriteSheet {
+    fn load(
+        asset_server: &AssetServer,
+        layouts: &mut Assets<TextureAtlasLayout>,
+        path: &'static str,
+        frame_size: UVec2,
+        columns: u32,
+        rows: u32,
+    ) -> Self {
+        let image = asset_server.load_with_settings(path, |settings: &mut ImageLoaderSettings| {
+            settings.sampler = ImageSampler::nearest();
+        });
+        let layout = layouts.add(TextureAtlasLayout::from_grid(
+            frame_size, columns, rows, None, None,
+        ));
+
+        Self {
+            image,
+            layout,
+            frame_size,
+        }
+    }
+
+    /// A sprite showing frame `index`, drawn at `scale` times the frame size
+    pub fn sprite(&self, index: usize, scale: f32) -> Sprite {
+        Sprite {
+            image: self.image.clone(),
+            texture_atlas: Some(self.atlas(index)),
+            custom_size: Some(self.frame_size.as_vec2() * scale),
+            ..default()
+        }
+    }
+
+    pub fn atlas(&self, index: usize) -> TextureAtlas {
+        TextureAtlas {
+            layout: self.layout.clone(),
+            index,
+        }
+    }
+}
+
+/// Lets assets that hold a sheet wait for its image to load
+impl VisitAssetDependencies for SpriteSheet {
+    fn visit_dependencies(&self, visit: &mut impl FnMut(UntypedAssetId)) {
+        self.image.visit_dependencies(visit);
+    }
+}
+
+/// Every sprite sheet in the game. Spawning code clones handles out of here
+/// instead of loading images or adding atlas layouts itself.
+#[derive(Resource)]
+pub struct SpriteAssets {
+    pub ship: SpriteSheet,
+    pub bullet: SpriteSheet,
+    pub explosion: SpriteSheet,
+    pub powerup: SpriteSheet,
+    pub button: SpriteSheet,
+}
+
+impl SpriteAssets {
+    /// Every sheet, e.g. to check how many assets the game holds
+    pub fn sheets(&self) -> [&SpriteSheet; 5] {
+        [
+            &self.ship,
+            &self.bullet,
+            &self.explosion,
+            &self.powerup,
+            &self.button,
+        ]
+    }
+}
+
+impl FromWorld for SpriteAssets {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>().clone();
+        let mut layouts = world.resource_mut::<Assets<TextureAtlasLayout>>();
+        let mut load = |path, frame_size, columns, rows| {
+            SpriteSheet::load(&asset_server, &mut layouts, path, frame_size, columns, rows)
+        };
+
+        Self {
+            ship: load("ship.png", UVec2::new(16, 24), 2, 5),
+            bullet: load("laser-bolts.png", UVec2::new(16, 16), 2, 2),
+            explosion: load("explosion.png", UVec2::splat(16), 5, 1),
+            powerup: load("powerup.png", UVec2::new(16, 16), 2, 4),
+            button: load("button-background.png", UVec2::splat(36), 2, 1),
+        }
+    }
+}
+
+fn add_sprites_to_manifest(mut manifest: ResMut<AssetManifest>, sprite_assets: Res<SpriteAssets>) {
+    for sheet in sprite_assets.sheets() {
+        manifest.add(sheet.image.clone());
+    }
+}
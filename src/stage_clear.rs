@@ -0,0 +1,75 @@
+use crate::{
+    game_state::GameState,
+    levels::{Levels, StageProgress},
+    loading::UiAssets,
+    theme::Palette,
+};
+use bevy::prelude::*;
+
+const STAGE_CLEAR_FONT_SIZE: f32 = 80.0;
+
+pub struct StageClearPlugin;
+impl Plugin for StageClearPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::LevelComplete), setup_stage_clear);
+    }
+}
+
+#[derive(Component)]
+struct StageClearText;
+
+fn setup_stage_clear(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    levels: Res<Levels>,
+    progress: Res<StageProgress>,
+) {
+    let font = ui_assets.font.clone();
+    let next_stage = progress.stage + 1;
+
+    commands
+        .spawn((
+            StageClearText,
+            StateScoped(GameState::LevelComplete),
+            Node {
+                height: Val::Percent(100.0),
+                width: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("STAGE CLEAR"),
+                TextFont {
+                    font: font.clone(),
+                    font_size: STAGE_CLEAR_FONT_SIZE,
+                    ..default()
+                },
+                TextColor(Palette::TEXT_PRIMARY),
+            ));
+
+            parent.spawn((
+                Text::new(format!(
+                    "Next: stage {} - {}",
+                    next_stage + 1,
+                    levels.stage(next_stage).name
+                )),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Palette::TEXT_PAUSED),
+                Node {
+                    margin: UiRect {
+                        top: Val::Px(20.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ));
+        });
+}
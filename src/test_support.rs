@@ -0,0 +1,472 @@
+use std::collections::BTreeMap;
+
+use bevy::{
+    ecs::{
+        schedule::{LogLevel, ScheduleBuildSettings},
+        system::RunSystemOnce,
+    },
+    input::{
+        gamepad::{
+            GamepadConnection, GamepadConnectionEvent, RawGamepadAxisChangedEvent,
+            RawGamepadButtonChangedEvent, RawGamepadEvent,
+        },
+        keyboard::{Key, KeyboardInput, NativeKey},
+        ButtonState,
+    },
+    prelude::*,
+    text::TextPlugin,
+};
+use bevy_rand::prelude::*;
+
+use crate::{
+    balance::Balance,
+    components::{Bullet, Health, PlayerStats, Shoot, WeaponLevel},
+    enemies::{spawn_enemy, Enemy, EnemyCount, EnemySpawning},
+    enemy_defs::{EnemyDefs, EnemyType},
+    enemy_movement::EnemyMovement,
+    enemy_weapons::EnemyProjectile,
+    explosion::Explosion,
+    game::{GamePlugin, GameRestartEvent, Score},
+    game_state::{GameState, GameStatePlugin},
+    headless::HeadlessPlugin,
+    hud::hud::HudPlugin,
+    levels::{Levels, StageProgress},
+    loading::{LoadingPlugin, LoadingProgress, UiAssets},
+    player::Player,
+    powerups::{spawn_powerup, Powerup, PowerupCount, PowerupType},
+    rng::RunSeed,
+    settings::Settings,
+    sprite_assets::SpriteAssets,
+    AppState,
+};
+
+pub const TEST_SEED: u64 = 0;
+
+/// Headless game for scripted gameplay scenarios.
+///
+/// Every `step` runs one frame, which is exactly one gameplay tick. Enemies
+/// don't spawn on their own, so a scenario only has the enemies it spawns.
+pub struct TestApp {
+    app: App,
+}
+
+impl TestApp {
+    /// A game that has just started its first run
+    pub fn new() -> Self {
+        Self::build(false, false)
+    }
+
+    /// A game where enemies spawn on their own, as they do in a real game
+    pub fn with_enemy_spawning() -> Self {
+        Self::build(true, false)
+    }
+
+    /// A game with the HUD. Its font isn't loaded, as nothing is drawn.
+    pub fn with_hud() -> Self {
+        Self::build(false, true)
+    }
+
+    fn build(enemy_spawning: bool, hud: bool) -> Self {
+        let mut settings = Settings::new();
+        settings.seed = Some(TEST_SEED);
+
+        let mut app = App::new();
+        app.add_plugins(HeadlessPlugin::default())
+            .add_plugins(EntropyPlugin::<WyRand>::default())
+            .add_plugins(LoadingPlugin {
+                next_state: AppState::Game,
+                exit_on_failure: false,
+            })
+            .insert_resource(settings)
+            .insert_state(AppState::Loading)
+            .add_plugins((GameStatePlugin, GamePlugin))
+            .insert_resource(EnemySpawning(enemy_spawning));
+        if hud {
+            app.insert_resource(UiAssets {
+                font: Handle::default(),
+                window_background: Handle::default(),
+            })
+            .add_plugins(TextPlugin)
+            .add_plugins(HudPlugin);
+        }
+        // Gameplay has to run in the same order every time, so any two
+        // fixed timestep systems that touch the same data must be ordered
+        app.edit_schedule(FixedUpdate, |schedule| {
+            schedule.set_build_settings(ScheduleBuildSettings {
+                ambiguity_detection: LogLevel::Error,
+                ..default()
+            });
+        });
+        // `App::run` would do this, and some asset loaders are only added here
+        app.finish();
+        app.cleanup();
+
+        let mut game = Self { app };
+        game.wait_for_loading();
+        game
+    }
+
+    /// Run frames until assets have loaded and the game has started
+    fn wait_for_loading(&mut self) {
+        for _ in 0..1000 {
+            self.app.update();
+            if let Some(error) = &self.world().resource::<LoadingProgress>().error {
+                panic!("{error}");
+            }
+            if *self.world().resource::<State<AppState>>() == AppState::Game {
+                return;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        panic!("assets should load within a second");
+    }
+
+    pub fn world(&self) -> &World {
+        self.app.world()
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        self.app.world_mut()
+    }
+
+    /// Run `frames` updates of the app
+    pub fn step(&mut self, frames: u32) {
+        for _ in 0..frames {
+            self.app.update();
+        }
+    }
+
+    /// Hold `key` down from the next frame on
+    pub fn press(&mut self, key: KeyCode) {
+        self.send_key(key, ButtonState::Pressed);
+    }
+
+    pub fn release(&mut self, key: KeyCode) {
+        self.send_key(key, ButtonState::Released);
+    }
+
+    fn send_key(&mut self, key_code: KeyCode, state: ButtonState) {
+        self.world_mut().send_event(KeyboardInput {
+            key_code,
+            logical_key: Key::Unidentified(NativeKey::Unidentified),
+            state,
+            repeat: false,
+            window: Entity::PLACEHOLDER,
+        });
+    }
+
+    /// Plug in a gamepad, ready for input on the next frame
+    pub fn connect_gamepad(&mut self) -> Entity {
+        let gamepad = self.world_mut().spawn_empty().id();
+        self.send_gamepad_connection(
+            gamepad,
+            GamepadConnection::Connected {
+                name: "Test gamepad".to_string(),
+                vendor_id: None,
+                product_id: None,
+            },
+        );
+        self.step(1);
+        gamepad
+    }
+
+    pub fn disconnect_gamepad(&mut self, gamepad: Entity) {
+        self.send_gamepad_connection(gamepad, GamepadConnection::Disconnected);
+    }
+
+    /// Sent both raw and processed, like the gamepad backend does
+    fn send_gamepad_connection(&mut self, gamepad: Entity, connection: GamepadConnection) {
+        let event = GamepadConnectionEvent::new(gamepad, connection);
+        self.world_mut()
+            .send_event(RawGamepadEvent::Connection(event.clone()));
+        self.world_mut().send_event(event);
+    }
+
+    /// Hold `button` of `gamepad` down from the next frame on
+    pub fn press_button(&mut self, gamepad: Entity, button: GamepadButton) {
+        self.send_button(gamepad, button, 1.0);
+    }
+
+    pub fn release_button(&mut self, gamepad: Entity, button: GamepadButton) {
+        self.send_button(gamepad, button, 0.0);
+    }
+
+    fn send_button(&mut self, gamepad: Entity, button: GamepadButton, value: f32) {
+        self.world_mut()
+            .send_event(RawGamepadEvent::Button(RawGamepadButtonChangedEvent::new(
+                gamepad, button, value,
+            )));
+    }
+
+    /// Hold the left stick of `gamepad` at `position` from the next frame on
+    pub fn move_stick(&mut self, gamepad: Entity, position: Vec2) {
+        for (axis, value) in [
+            (GamepadAxis::LeftStickX, position.x),
+            (GamepadAxis::LeftStickY, position.y),
+        ] {
+            self.world_mut()
+                .send_event(RawGamepadEvent::Axis(RawGamepadAxisChangedEvent::new(
+                    gamepad, axis, value,
+                )));
+        }
+    }
+
+    /// The enemy defined in `assets/enemies/<name>.enemy.ron`
+    pub fn enemy_type(&self, name: &str) -> EnemyType {
+        self.world()
+            .resource::<EnemyDefs>()
+            .find(name)
+            .unwrap_or_else(|| panic!("there should be a {name} enemy"))
+    }
+
+    /// Spawn the enemy named after its definition file, e.g. `small`
+    pub fn spawn_enemy(&mut self, name: &str, position: Vec2) -> Entity {
+        let movement = self
+            .world()
+            .resource::<EnemyDefs>()
+            .get(self.enemy_type(name))
+            .movement
+            .clone();
+        self.spawn_moving_enemy(name, position, movement)
+    }
+
+    /// Spawn an enemy like `spawn_enemy`, moving along `movement` instead of
+    /// the way its file says
+    pub fn spawn_moving_enemy(
+        &mut self,
+        name: &str,
+        position: Vec2,
+        movement: EnemyMovement,
+    ) -> Entity {
+        let enemy_type = self.enemy_type(name);
+        self.world_mut()
+            .run_system_once(
+                move |mut commands: Commands,
+                      enemy_defs: Res<EnemyDefs>,
+                      mut enemy_count: ResMut<EnemyCount>| {
+                    spawn_enemy(
+                        &mut commands,
+                        &enemy_defs,
+                        &mut enemy_count,
+                        enemy_type,
+                        position.extend(1.0),
+                        movement.clone(),
+                    )
+                },
+            )
+            .expect("enemy should spawn")
+    }
+
+    pub fn spawn_powerup(&mut self, powerup_type: PowerupType, position: Vec2) -> Entity {
+        self.world_mut()
+            .run_system_once(
+                move |mut commands: Commands,
+                      sprite_assets: Res<SpriteAssets>,
+                      mut powerup_count: ResMut<PowerupCount>| {
+                    spawn_powerup(
+                        &mut commands,
+                        &sprite_assets,
+                        &mut powerup_count,
+                        powerup_type,
+                        position.extend(1.0),
+                    )
+                },
+            )
+            .expect("powerup should spawn")
+    }
+
+    /// Replace the stages from `assets/campaign.levels.ron` with `ron`, written
+    /// the same way, and start a new run with them
+    pub fn load_levels(&mut self, ron: &str) {
+        let levels: Levels = ron::de::from_str(ron).expect("levels should parse");
+        self.world_mut().insert_resource(levels);
+        self.restart();
+        self.step(1);
+    }
+
+    pub fn stage_progress(&self) -> (usize, usize) {
+        let progress = self.world().resource::<StageProgress>();
+        (progress.stage, progress.wave)
+    }
+
+    /// Start a new run the same way the restart key does
+    pub fn restart(&mut self) {
+        self.world_mut()
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::Playing);
+        self.world_mut().send_event(GameRestartEvent);
+    }
+
+    /// Leave the game for the main menu the same way the menu key does
+    pub fn quit_to_menu(&mut self) {
+        self.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Menu);
+    }
+
+    /// Start a game from the menu
+    pub fn start_game(&mut self) {
+        self.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Game);
+    }
+
+    /// Number of entities with a `T` component
+    pub fn count<T: Component>(&mut self) -> usize {
+        self.world_mut()
+            .query_filtered::<(), With<T>>()
+            .iter(self.app.world())
+            .count()
+    }
+
+    pub fn score(&self) -> usize {
+        **self.world().resource::<Score>()
+    }
+
+    pub fn enemy_count(&self) -> usize {
+        self.world().resource::<EnemyCount>().total()
+    }
+
+    pub fn powerup_count(&self) -> usize {
+        self.world().resource::<PowerupCount>().0
+    }
+
+    pub fn game_state(&self) -> GameState {
+        *self.world().resource::<State<GameState>>().get()
+    }
+
+    /// Edit the loaded balance file, as if it was changed on disk
+    pub fn edit_balance(&mut self, edit: impl Fn(&mut Balance)) {
+        let mut assets = self.world_mut().resource_mut::<Assets<Balance>>();
+        let ids: Vec<_> = assets.ids().collect();
+        for id in ids {
+            edit(assets.get_mut(id).unwrap());
+        }
+    }
+
+    /// Number of images and of atlas layouts the game holds
+    pub fn sprite_asset_counts(&self) -> (usize, usize) {
+        (
+            self.world().resource::<Assets<Image>>().len(),
+            self.world().resource::<Assets<TextureAtlasLayout>>().len(),
+        )
+    }
+
+    pub fn player(&mut self) -> Entity {
+        self.world_mut()
+            .query_filtered::<Entity, With<Player>>()
+            .single(self.app.world())
+    }
+
+    pub fn player_position(&mut self) -> Vec2 {
+        let player = self.player();
+        self.world()
+            .get::<Transform>(player)
+            .unwrap()
+            .translation
+            .truncate()
+    }
+
+    pub fn player_health(&mut self) -> i32 {
+        let player = self.player();
+        self.world().get::<Health>(player).unwrap().0
+    }
+
+    pub fn set_player_health(&mut self, health: i32) {
+        let player = self.player();
+        self.world_mut().get_mut::<Health>(player).unwrap().0 = health;
+    }
+
+    pub fn player_stats(&mut self) -> PlayerStats {
+        let player = self.player();
+        self.world().get::<PlayerStats>(player).unwrap().clone()
+    }
+
+    pub fn weapon_level(&mut self) -> u8 {
+        let player = self.player();
+        self.world().get::<WeaponLevel>(player).unwrap().0
+    }
+
+    pub fn set_weapon_level(&mut self, level: u8) {
+        let player = self.player();
+        self.world_mut().get_mut::<WeaponLevel>(player).unwrap().0 = level;
+    }
+
+    /// Everything that makes up the state of a run
+    pub fn snapshot(&mut self) -> RunSnapshot {
+        let (player_position, player_health, player_stats, weapon_level, shoot_elapsed) = self
+            .world_mut()
+            .query_filtered::<(&Transform, &Health, &PlayerStats, &WeaponLevel, &Shoot), With<Player>>()
+            .get_single(self.app.world())
+            .map(|(transform, health, stats, weapon_level, shoot)| {
+                (
+                    Some(transform.translation),
+                    health.0,
+                    (stats.fire_rate, stats.speed),
+                    weapon_level.0,
+                    shoot.timer.elapsed_secs(),
+                )
+            })
+            .unwrap_or_default();
+
+        let mut enemies: Vec<_> = self
+            .world_mut()
+            .query::<(&Transform, &Enemy, &Health)>()
+            .iter(self.app.world())
+            .map(|(transform, enemy, health)| {
+                (transform.translation.to_array(), enemy.enemy_type, health.0)
+            })
+            .collect();
+        enemies.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let mut powerups = self.translations::<Powerup>();
+        powerups.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let mut bullets = self.translations::<Bullet>();
+        bullets.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let mut enemy_projectiles = self.translations::<EnemyProjectile>();
+        enemy_projectiles.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        RunSnapshot {
+            seed: self.world().get_resource::<RunSeed>().map(|seed| seed.0),
+            score: self.score(),
+            enemy_count: self.world().resource::<EnemyCount>().0.clone(),
+            powerup_count: self.powerup_count(),
+            player_position,
+            player_health,
+            player_stats,
+            weapon_level,
+            shoot_elapsed,
+            enemies,
+            powerups,
+            bullets,
+            enemy_projectiles,
+            explosions: self.count::<Explosion>(),
+        }
+    }
+
+    fn translations<T: Component>(&mut self) -> Vec<[f32; 3]> {
+        self.world_mut()
+            .query_filtered::<&Transform, With<T>>()
+            .iter(self.app.world())
+            .map(|transform| transform.translation.to_array())
+            .collect()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RunSnapshot {
+    pub seed: Option<u64>,
+    pub score: usize,
+    pub enemy_count: BTreeMap<EnemyType, usize>,
+    pub powerup_count: usize,
+    pub player_position: Option<Vec3>,
+    pub player_health: i32,
+    pub player_stats: (f32, f32),
+    pub weapon_level: u8,
+    pub shoot_elapsed: f32,
+    pub enemies: Vec<([f32; 3], EnemyType, i32)>,
+    pub powerups: Vec<[f32; 3]>,
+    pub bullets: Vec<[f32; 3]>,
+    pub enemy_projectiles: Vec<[f32; 3]>,
+    pub explosions: usize,
+}
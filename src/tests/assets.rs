@@ -0,0 +1,70 @@
+use bevy::prelude::*;
+
+use crate::{
+    enemy_defs::EnemyDefs, powerups::PowerupType, sprite_assets::SpriteAssets,
+    test_support::TestApp,
+};
+
+#[test]
+fn sprite_assets_stay_flat_over_a_session() {
+    let mut game = TestApp::with_enemy_spawning();
+    let counts = game.sprite_asset_counts();
+    let sheets = game.world().resource::<SpriteAssets>().sheets().len()
+        + game.world().resource::<EnemyDefs>().iter().count();
+    assert_eq!(counts.1, sheets);
+
+    // Shoot, spawn, explode and restart for a good while
+    game.press(KeyCode::Space);
+    for run in 0..3 {
+        for _ in 0..5 {
+            game.spawn_enemy("medium", Vec2::new(0.0, 200.0));
+            game.spawn_powerup(PowerupType::Speed, Vec2::new(100.0, 200.0));
+            game.step(200);
+        }
+        assert_eq!(game.sprite_asset_counts(), counts, "run {run}");
+        game.restart();
+        game.step(1);
+    }
+    assert_eq!(game.sprite_asset_counts(), counts);
+}
+
+#[test]
+fn enemies_are_defined_by_their_files() {
+    let mut game = TestApp::new();
+    let names: Vec<_> = game
+        .world()
+        .resource::<EnemyDefs>()
+        .iter()
+        .map(|(_, def)| def.name.clone())
+        .collect();
+    assert_eq!(names, ["large", "medium", "mothership", "small"]);
+
+    let position = game.player_position() + Vec2::new(0.0, 350.0);
+    game.spawn_enemy("medium", position);
+    game.press(KeyCode::Space);
+    game.step(240);
+
+    assert_eq!(game.enemy_count(), 0);
+    assert_eq!(game.score(), 12);
+}
+
+#[test]
+fn every_enemy_file_is_listed() {
+    let game = TestApp::new();
+    let mut files: Vec<_> = std::fs::read_dir("assets/enemies")
+        .unwrap()
+        .filter_map(|entry| {
+            let name = entry.unwrap().file_name().into_string().unwrap();
+            name.strip_suffix(".enemy.ron").map(str::to_string)
+        })
+        .collect();
+    files.sort();
+
+    let names: Vec<_> = game
+        .world()
+        .resource::<EnemyDefs>()
+        .iter()
+        .map(|(_, def)| def.name.clone())
+        .collect();
+    assert_eq!(names, files);
+}
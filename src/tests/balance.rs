@@ -0,0 +1,24 @@
+use bevy::prelude::*;
+
+use crate::{balance::Balance, test_support::TestApp};
+
+#[test]
+fn balance_edits_apply_to_a_running_game() {
+    let mut game = TestApp::new();
+    let start = game.player_position();
+
+    // Edited assets are announced at the end of the frame, so the game picks
+    // them up in the next one
+    game.edit_balance(|balance| balance.player_speed = 400.0);
+    game.step(2);
+    assert_eq!(game.world().resource::<Balance>().player_speed, 400.0);
+
+    // 64 ticks are one second
+    game.press(KeyCode::ArrowRight);
+    game.step(32);
+    game.release(KeyCode::ArrowRight);
+    game.step(1);
+
+    let moved = game.player_position() - start;
+    assert!((moved.x - 200.0).abs() <= 400.0 / 64.0, "moved {moved}");
+}
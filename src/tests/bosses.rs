@@ -0,0 +1,175 @@
+use bevy::prelude::*;
+
+use crate::{
+    bosses::Boss,
+    components::Health,
+    enemies::Enemy,
+    enemy_movement::EnemyMovement,
+    explosion::Explosion,
+    hud::hud::{BossHealthBar, BossHealthFill},
+    play_area::PlayArea,
+    test_support::TestApp,
+};
+
+fn set_health(game: &mut TestApp, enemy: Entity, health: i32) {
+    game.world_mut().get_mut::<Health>(enemy).unwrap().0 = health;
+}
+
+fn health(game: &TestApp, enemy: Entity) -> i32 {
+    game.world().get::<Health>(enemy).unwrap().0
+}
+
+#[test]
+fn bosses_change_phase_as_they_lose_health() {
+    let mut game = TestApp::new();
+    let boss = game.spawn_enemy("mothership", Vec2::new(0.0, 300.0));
+    game.step(1);
+    assert_eq!(game.world().get::<Boss>(boss).unwrap().max_health, 150);
+    let patrol = |amplitude| EnemyMovement::Patrol {
+        y: 200.0,
+        amplitude,
+        period: if amplitude == 200.0 { 6.0 } else { 4.0 },
+    };
+    assert_eq!(
+        game.world().get::<EnemyMovement>(boss),
+        Some(&patrol(200.0))
+    );
+
+    // Phases start at or below their share of the boss's health
+    set_health(&mut game, boss, 91);
+    game.step(1);
+    assert_eq!(
+        game.world().get::<EnemyMovement>(boss),
+        Some(&patrol(200.0))
+    );
+    set_health(&mut game, boss, 90);
+    game.step(1);
+    assert_eq!(
+        game.world().get::<EnemyMovement>(boss),
+        Some(&patrol(350.0))
+    );
+}
+
+#[test]
+fn bosses_keep_patrolling_the_same_spot_across_phases() {
+    let mut game = TestApp::new();
+    let boss = game.spawn_enemy("mothership", Vec2::new(0.0, 300.0));
+    let half_area = game.world().resource::<PlayArea>().half_size();
+
+    // Change phase partway through each sway, again and again
+    for health in [150, 90, 89, 45, 44, 30, 10] {
+        set_health(&mut game, boss, health);
+        for _ in 0..150 {
+            game.step(1);
+            let x = game.world().get::<Transform>(boss).unwrap().translation.x;
+            assert!(x.abs() <= 400.0 + 0.01, "{x} is outside the patrol");
+            assert!(x.abs() < half_area.x);
+        }
+    }
+}
+
+#[test]
+fn boss_core_takes_more_damage_than_its_hull() {
+    let mut game = TestApp::new();
+    // Bullets fly up into the core, below the middle of the boss
+    let boss = game.spawn_enemy("mothership", Vec2::new(0.0, 250.0));
+    game.press(KeyCode::Space);
+    game.step(60);
+    assert_eq!(health(&game, boss), 150 - 3);
+
+    // Off to the side, they hit the armored wings instead
+    let mut game = TestApp::new();
+    let boss = game.spawn_enemy("mothership", Vec2::new(-60.0, 250.0));
+    game.press(KeyCode::Space);
+    game.step(60);
+    assert_eq!(health(&game, boss), 150 - 1);
+}
+
+#[test]
+fn waves_wait_for_the_boss_to_go_down() {
+    let mut game = TestApp::with_enemy_spawning();
+    game.load_levels(
+        r#"(
+            stage_clear_time: 1.0,
+            difficulty_step: (speed: 0.0, health: 0.0, fire_rate: 0.0),
+            stages: [(name: "Test", waves: [(delay: 0.0, spawns: [
+                Group(at: 0.0, enemy: "mothership", count: 1, x: 0.0, formation: Row(spacing: 0.0)),
+                Group(at: 0.5, enemy: "small", count: 1, x: 300.0, formation: Row(spacing: 0.0)),
+            ])])],
+        )"#,
+    );
+    game.step(64);
+    assert_eq!(game.enemy_count(), 1);
+
+    let boss = game
+        .world_mut()
+        .query_filtered::<Entity, With<Boss>>()
+        .single(game.world());
+    set_health(&mut game, boss, 0);
+    game.step(1);
+    assert_eq!(game.score(), 500);
+
+    // It goes out with a string of explosions, and the wave carries on
+    game.step(40);
+    assert!(game.count::<Explosion>() > 3);
+    assert_eq!(game.enemy_count(), 1);
+    let enemy = game
+        .world_mut()
+        .query::<&Enemy>()
+        .single(game.world())
+        .enemy_type;
+    assert_eq!(enemy, game.enemy_type("small"));
+}
+
+#[test]
+fn score_bosses_come_in_once_the_score_is_reached() {
+    let mut game = TestApp::with_enemy_spawning();
+    game.load_levels(
+        r#"(
+            stage_clear_time: 1.0,
+            difficulty_step: (speed: 0.0, health: 0.0, fire_rate: 0.0),
+            stages: [(name: "Test", waves: [(delay: 0.0, spawns: [
+                Group(at: 0.0, enemy: "small", count: 2, x: 150.0, formation: Row(spacing: 300.0)),
+            ])])],
+            score_bosses: [(score: 2, enemy: "mothership")],
+        )"#,
+    );
+
+    game.press(KeyCode::Space);
+    while game.score() == 0 {
+        game.step(1);
+    }
+    game.step(2);
+    assert_eq!(game.count::<Boss>(), 1);
+}
+
+#[test]
+fn the_boss_health_bar_follows_the_boss_closest_to_going_down() {
+    let mut game = TestApp::with_hud();
+    let bar = |game: &mut TestApp| {
+        let world = game.world_mut();
+        let visibility = *world
+            .query_filtered::<&Visibility, With<BossHealthBar>>()
+            .single(world);
+        let width = world
+            .query_filtered::<&Node, With<BossHealthFill>>()
+            .single(world)
+            .width;
+        (visibility, width)
+    };
+    game.step(1);
+    assert_eq!(bar(&mut game).0, Visibility::Hidden);
+
+    // A score boss joins a wave's boss fight
+    let first = game.spawn_enemy("mothership", Vec2::new(-200.0, 250.0));
+    game.spawn_enemy("mothership", Vec2::new(200.0, 250.0));
+    game.step(1);
+    set_health(&mut game, first, 30);
+    game.step(1);
+    assert_eq!(bar(&mut game), (Visibility::Inherited, Val::Percent(20.0)));
+
+    set_health(&mut game, first, 0);
+    game.step(2);
+    assert_eq!(game.count::<Boss>(), 1);
+    assert_eq!(bar(&mut game), (Visibility::Inherited, Val::Percent(100.0)));
+}
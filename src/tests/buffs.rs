@@ -0,0 +1,35 @@
+use crate::{powerups::PowerupType, test_support::TestApp};
+
+#[test]
+fn buffs_wear_off_after_their_duration() {
+    let mut game = TestApp::new();
+    game.edit_balance(|balance| balance.fire_rate_buff.duration = 0.5);
+    game.step(2);
+    let position = game.player_position();
+
+    game.spawn_powerup(PowerupType::FireRate, position);
+    game.step(1);
+    assert_eq!(game.player_stats().fire_rate, 1.5);
+
+    // 64 ticks are one second
+    game.step(32);
+    assert_eq!(game.player_stats().fire_rate, 1.0);
+}
+
+#[test]
+fn maxed_buffs_turn_into_score() {
+    let mut game = TestApp::new();
+    let position = game.player_position();
+
+    // Fire rate stacks up to three times, and speed is capped at 1.5
+    for _ in 0..4 {
+        game.spawn_powerup(PowerupType::FireRate, position);
+        game.spawn_powerup(PowerupType::Speed, position);
+    }
+    game.step(1);
+
+    let stats = game.player_stats();
+    assert_eq!(stats.fire_rate, 1.5 * 1.5 * 1.5);
+    assert_eq!(stats.speed, 1.5);
+    assert_eq!(game.score(), 2 * 10);
+}
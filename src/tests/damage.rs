@@ -0,0 +1,99 @@
+use bevy::prelude::*;
+
+use crate::{
+    collisions::{DamageSource, PlayerDamagedEvent, PlayerHurtEvent},
+    components::Health,
+    enemy_weapons::EnemyProjectile,
+    test_support::TestApp,
+};
+
+#[test]
+fn player_is_invincible_for_a_while_after_a_hit() {
+    let mut game = TestApp::new();
+    let position = game.player_position();
+    game.spawn_enemy("small", position);
+    game.step(2);
+    assert_eq!(game.player_health(), 8);
+
+    // Flying into another enemy right away does nothing to either
+    let position = game.player_position();
+    game.spawn_enemy("small", position);
+    game.step(2);
+    assert_eq!(game.player_health(), 8);
+    assert_eq!(game.enemy_count(), 1);
+
+    // 64 ticks are one second
+    game.step(96);
+    let position = game.player_position();
+    game.spawn_enemy("small", position);
+    game.step(2);
+    assert_eq!(game.player_health(), 6);
+}
+
+#[test]
+fn hits_push_the_player_away() {
+    let mut game = TestApp::new();
+    let start = game.player_position();
+    game.spawn_enemy("small", start + Vec2::new(20.0, 0.0));
+
+    game.step(10);
+
+    let moved = game.player_position() - start;
+    assert!(moved.x < -10.0, "moved {moved}");
+}
+
+#[test]
+fn damage_events_carry_the_amount_and_source() {
+    let mut game = TestApp::new();
+    let position = game.player_position();
+    game.spawn_enemy("large", position);
+    game.step(1);
+
+    let events = game.world().resource::<Events<PlayerDamagedEvent>>();
+    let damage: Vec<_> = events
+        .get_cursor()
+        .read(events)
+        .map(|event| (event.amount, event.source))
+        .collect();
+    // A large enemy has 20 health, but hits for its contact damage at most
+    assert_eq!(damage, [(5, DamageSource::Enemy(game.enemy_type("large")))]);
+}
+
+#[test]
+fn running_into_a_boss_hurts_both_a_little() {
+    let mut game = TestApp::new();
+    // Its hull, well above its core
+    let position = game.player_position() - Vec2::new(0.0, 10.0);
+    let boss = game.spawn_enemy("mothership", position);
+    game.step(1);
+
+    assert_eq!(game.player_health(), 10 - 3);
+    assert_eq!(game.world().get::<Health>(boss).unwrap().0, 150 - 10);
+}
+
+#[test]
+fn only_the_first_of_two_hits_in_a_tick_hurts() {
+    let mut game = TestApp::new();
+    let position = game.player_position();
+    game.spawn_enemy("small", position);
+    game.world_mut().spawn((
+        EnemyProjectile {
+            velocity: Vec2::ZERO,
+            damage: 1,
+        },
+        Transform::from_translation(position.extend(1.0)),
+    ));
+    game.step(1);
+
+    // Both hits land, but only the enemy's gets through
+    let events = game.world().resource::<Events<PlayerDamagedEvent>>();
+    assert_eq!(events.get_cursor().read(events).count(), 2);
+    let events = game.world().resource::<Events<PlayerHurtEvent>>();
+    let hurt: Vec<_> = events
+        .get_cursor()
+        .read(events)
+        .map(|event| (event.amount, event.source))
+        .collect();
+    assert_eq!(hurt, [(2, DamageSource::Enemy(game.enemy_type("small")))]);
+    assert_eq!(game.player_health(), 8);
+}
@@ -0,0 +1,136 @@
+use bevy::prelude::*;
+
+use crate::{enemy_movement::EnemyMovement, test_support::TestApp};
+
+fn enemy_position(game: &mut TestApp, enemy: Entity) -> Option<Vec2> {
+    game.world()
+        .get::<Transform>(enemy)
+        .map(|transform| transform.translation.truncate())
+}
+
+/// Positions of an enemy every `every` frames, until it is gone or `frames`
+/// have passed
+fn track(game: &mut TestApp, enemy: Entity, frames: u32, every: u32) -> Vec<Vec2> {
+    let mut positions = Vec::new();
+    for _ in 0..frames / every {
+        game.step(every);
+        match enemy_position(game, enemy) {
+            Some(position) => positions.push(position),
+            None => break,
+        }
+    }
+    positions
+}
+
+#[test]
+fn weaving_enemies_sway_around_where_they_spawned() {
+    let mut game = TestApp::new();
+    let movement = EnemyMovement::Weave {
+        amplitude: 100.0,
+        period: 1.0,
+    };
+    let enemy = game.spawn_moving_enemy("small", Vec2::new(300.0, 300.0), movement);
+
+    // A quarter period in, it is at the far right of its sway
+    let positions = track(&mut game, enemy, 64, 16);
+    assert!((positions[0].x - 400.0).abs() < 1.0, "{positions:?}");
+    assert!((positions[2].x - 200.0).abs() < 1.0, "{positions:?}");
+    assert!(positions.windows(2).all(|pair| pair[1].y < pair[0].y));
+}
+
+#[test]
+fn diving_enemies_head_for_the_player() {
+    let mut game = TestApp::new();
+    let player = game.player_position();
+    let movement = EnemyMovement::Dive {
+        delay: 0.5,
+        speed: 400.0,
+    };
+    let enemy = game.spawn_moving_enemy("medium", Vec2::new(-300.0, 300.0), movement);
+
+    game.step(32);
+    let dive_start = enemy_position(&mut game, enemy).unwrap();
+    game.step(16);
+    let diving = enemy_position(&mut game, enemy).unwrap();
+
+    let towards_player = (player - dive_start).normalize();
+    let heading = (diving - dive_start).normalize();
+    assert!(
+        heading.dot(towards_player) > 0.99,
+        "{heading} {towards_player}"
+    );
+}
+
+#[test]
+fn strafing_enemies_stop_sideways_then_carry_on() {
+    let mut game = TestApp::new();
+    let movement = EnemyMovement::Strafe {
+        y: 200.0,
+        distance: 150.0,
+    };
+    let enemy = game.spawn_moving_enemy("small", Vec2::new(-300.0, 300.0), movement);
+
+    let positions = track(&mut game, enemy, 256, 4);
+    assert!(positions
+        .iter()
+        .all(|position| position.y >= 200.0 || position.x == -150.0));
+    assert!(positions
+        .iter()
+        .any(|position| position.y == 200.0 && position.x > -300.0));
+    assert!(positions.last().unwrap().y < 200.0);
+}
+
+#[test]
+fn hovering_enemies_wait_then_leave() {
+    let mut game = TestApp::new();
+    let movement = EnemyMovement::Hover {
+        y: 250.0,
+        duration: 1.0,
+    };
+    let enemy = game.spawn_moving_enemy("medium", Vec2::new(0.0, 300.0), movement);
+
+    game.step(96);
+    assert_eq!(enemy_position(&mut game, enemy).unwrap().y, 250.0);
+
+    // Back up and out of the top, where it is removed
+    game.step(256);
+    assert_eq!(enemy_position(&mut game, enemy), None);
+    assert_eq!(game.enemy_count(), 0);
+}
+
+#[test]
+fn path_enemies_pass_through_every_point() {
+    let mut game = TestApp::new();
+    let start = Vec2::new(0.0, 300.0);
+    let points = vec![Vec2::new(200.0, -100.0), Vec2::new(-200.0, -200.0)];
+    let movement = EnemyMovement::Path {
+        points: points.clone(),
+    };
+    let enemy = game.spawn_moving_enemy("small", start, movement);
+
+    let positions = track(&mut game, enemy, 512, 1);
+    for point in points {
+        let closest = positions
+            .iter()
+            .map(|position| position.distance(start + point))
+            .fold(f32::MAX, f32::min);
+        assert!(closest < 2.0, "{point} missed by {closest}");
+    }
+    // Then it carries on straight down
+    let last = positions.last().unwrap();
+    assert!((last.x - -200.0).abs() < 2.0 && last.y < 100.0, "{last}");
+}
+
+#[test]
+fn mirrored_movement_swaps_left_and_right() {
+    let movement = EnemyMovement::Path {
+        points: vec![Vec2::new(50.0, -10.0)],
+    };
+    assert_eq!(
+        movement.mirrored(),
+        EnemyMovement::Path {
+            points: vec![Vec2::new(-50.0, -10.0)]
+        }
+    );
+    assert_eq!(EnemyMovement::Descend.mirrored(), EnemyMovement::Descend);
+}
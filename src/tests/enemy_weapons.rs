@@ -0,0 +1,30 @@
+use bevy::prelude::*;
+
+use crate::{enemy_weapons::EnemyProjectile, test_support::TestApp};
+
+#[test]
+fn aimed_shots_hurt_the_player() {
+    let mut game = TestApp::new();
+    let health = game.player_health();
+
+    // Off to the side, so only its shots can reach the player
+    game.spawn_enemy("medium", Vec2::new(200.0, 300.0));
+    game.step(140);
+    assert_eq!(game.count::<EnemyProjectile>(), 1);
+
+    game.step(120);
+    assert_eq!(game.player_health(), health - 1);
+}
+
+#[test]
+fn spread_shots_fan_out_and_leave_the_screen() {
+    let mut game = TestApp::new();
+
+    let enemy = game.spawn_enemy("large", Vec2::new(400.0, 300.0));
+    game.step(170);
+    assert_eq!(game.count::<EnemyProjectile>(), 5);
+
+    game.world_mut().despawn(enemy);
+    game.step(400);
+    assert_eq!(game.count::<EnemyProjectile>(), 0);
+}
@@ -0,0 +1,212 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::{Bullet, Health},
+    enemies::Enemy,
+    game_state::GameState,
+    player::Player,
+    powerups::{Powerup, PowerupType},
+    test_support::TestApp,
+    AppState,
+};
+
+#[test]
+fn bullets_kill_enemy() {
+    let mut game = TestApp::new();
+    game.spawn_enemy("small", Vec2::new(0.0, 250.0));
+
+    game.press(KeyCode::Space);
+    game.step(90);
+
+    assert_eq!(game.enemy_count(), 0);
+    assert_eq!(game.score(), 2);
+    assert_eq!(game.player_health(), 10);
+}
+
+#[test]
+fn player_moves_with_input() {
+    let mut game = TestApp::new();
+    let start = game.player_position();
+
+    game.press(KeyCode::ArrowRight);
+    game.step(32);
+    game.release(KeyCode::ArrowRight);
+    game.step(1);
+    let moved = game.player_position();
+    game.step(32);
+
+    assert!(moved.x > start.x);
+    assert_eq!(moved.y, start.y);
+    assert_eq!(game.player_position(), moved);
+}
+
+#[test]
+fn enemy_contact_trades_health() {
+    let mut game = TestApp::new();
+    let position = game.player_position();
+    game.spawn_enemy("small", position);
+
+    game.step(2);
+
+    // Both lose the other's health, which destroys the small enemy
+    assert_eq!(game.player_health(), 8);
+    assert_eq!(game.enemy_count(), 0);
+    assert_eq!(game.score(), 2);
+    assert_eq!(game.game_state(), GameState::Playing);
+}
+
+#[test]
+fn enemy_contact_ends_game_when_health_runs_out() {
+    let mut game = TestApp::new();
+    game.set_player_health(4);
+    let position = game.player_position();
+    game.spawn_enemy("large", position);
+
+    game.step(3);
+
+    // A large enemy does at most 5 damage by contact
+    assert_eq!(game.player_health(), -1);
+    assert_eq!(game.enemy_count(), 1);
+    assert_eq!(game.game_state(), GameState::GameOver);
+}
+
+#[test]
+fn powerup_pickup_boosts_player() {
+    let mut game = TestApp::new();
+    let position = game.player_position();
+    game.spawn_powerup(PowerupType::Speed, position);
+    game.spawn_powerup(PowerupType::FireRate, position);
+
+    game.step(1);
+
+    assert_eq!(game.powerup_count(), 0);
+    let stats = game.player_stats();
+    assert_eq!(stats.speed, 1.2);
+    assert_eq!(stats.fire_rate, 1.5);
+}
+
+#[test]
+fn weapon_levels_fire_wider_volleys() {
+    for (level, bolts) in [(1, 1), (2, 2), (3, 3), (4, 6)] {
+        let mut game = TestApp::new();
+        game.set_weapon_level(level);
+        game.press(KeyCode::Space);
+        while game.count::<Bullet>() == 0 {
+            game.step(1);
+        }
+        assert_eq!(game.count::<Bullet>(), bolts, "weapon level {level}");
+    }
+}
+
+#[test]
+fn weapon_upgrades_stack_and_damage_takes_one_away() {
+    let mut game = TestApp::new();
+    let position = game.player_position();
+    for _ in 0..5 {
+        game.spawn_powerup(PowerupType::WeaponUpgrade, position);
+    }
+    game.step(1);
+    assert_eq!(game.weapon_level(), 4);
+
+    game.spawn_enemy("small", position);
+    game.step(2);
+    assert_eq!(game.weapon_level(), 3);
+}
+
+#[test]
+fn health_pickup_heals_up_to_max_health() {
+    let mut game = TestApp::new();
+    let position = game.player_position();
+    game.set_player_health(5);
+
+    game.spawn_powerup(PowerupType::HealthBoost, position);
+    game.step(1);
+    assert_eq!(game.player_health(), 8);
+
+    game.spawn_powerup(PowerupType::HealthBoost, position);
+    game.step(1);
+    assert_eq!(game.player_health(), 10);
+}
+
+#[test]
+fn enemies_drop_health_when_the_player_is_low() {
+    let mut game = TestApp::new();
+    game.edit_balance(|balance| balance.low_health_drop_chance = 1.0);
+    let destroy_small = |game: &mut TestApp| {
+        let enemy = game.spawn_enemy("small", Vec2::new(200.0, 250.0));
+        game.world_mut().get_mut::<Health>(enemy).unwrap().0 = 0;
+        game.step(2);
+    };
+
+    // Small enemies drop nothing on their own
+    destroy_small(&mut game);
+    assert_eq!(game.powerup_count(), 0);
+
+    game.set_player_health(5);
+    destroy_small(&mut game);
+    let powerups: Vec<_> = game
+        .world_mut()
+        .query::<&Powerup>()
+        .iter(game.world())
+        .map(|powerup| powerup.powerup_type)
+        .collect();
+    assert!(matches!(powerups[..], [PowerupType::HealthBoost]));
+}
+
+#[test]
+fn restart_starts_a_new_run() {
+    let mut game = TestApp::new();
+    game.set_player_health(1);
+    let position = game.player_position();
+    game.spawn_enemy("large", position);
+    game.spawn_powerup(PowerupType::Speed, Vec2::new(200.0, 200.0));
+    game.step(3);
+    assert_eq!(game.game_state(), GameState::GameOver);
+
+    game.restart();
+    game.step(2);
+
+    assert_eq!(game.game_state(), GameState::Playing);
+    assert_eq!(game.player_health(), 10);
+    assert_eq!(game.enemy_count(), 0);
+    assert_eq!(game.powerup_count(), 0);
+}
+
+#[test]
+fn quit_to_menu_tears_down_the_game() {
+    let mut game = TestApp::new();
+    game.spawn_enemy("small", Vec2::new(0.0, 250.0));
+    game.spawn_enemy("medium", Vec2::new(-200.0, 250.0));
+    game.spawn_powerup(PowerupType::Speed, Vec2::new(200.0, 250.0));
+    game.press(KeyCode::Space);
+    game.step(90);
+    assert!(game.score() > 0);
+
+    game.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Paused);
+    game.step(1);
+    game.quit_to_menu();
+    game.step(1);
+
+    assert_eq!(
+        *game.world().resource::<State<AppState>>().get(),
+        AppState::Menu
+    );
+    assert!(!game.world().contains_resource::<State<GameState>>());
+    assert_eq!(game.count::<Player>(), 0);
+    assert_eq!(game.count::<Enemy>(), 0);
+    assert_eq!(game.count::<Bullet>(), 0);
+    assert_eq!(game.count::<Powerup>(), 0);
+    assert_eq!(game.enemy_count(), 0);
+    assert_eq!(game.powerup_count(), 0);
+    assert_eq!(game.score(), 0);
+
+    game.release(KeyCode::Space);
+    game.start_game();
+    game.step(1);
+
+    assert_eq!(game.count::<Player>(), 1);
+    assert_eq!(game.player_health(), 10);
+    assert_eq!(game.game_state(), GameState::Playing);
+}
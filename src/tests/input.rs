@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::Bullet,
+    game_state::GameState,
+    input::{Action, InputBindings, PlayerInput},
+    settings::Settings,
+    test_support::TestApp,
+};
+
+#[test]
+fn rebound_keys_drive_the_player() {
+    let mut game = TestApp::new();
+    {
+        let mut settings = game.world_mut().resource_mut::<Settings>();
+        settings.bindings.unbind(Action::Fire, 0);
+        settings.bindings.bind(Action::Fire, None, KeyCode::KeyF);
+        settings
+            .bindings
+            .bind(Action::MoveRight, Some(0), KeyCode::KeyL);
+    }
+
+    // The old keys do nothing
+    let start = game.player_position();
+    game.press(KeyCode::Space);
+    game.press(KeyCode::ArrowRight);
+    game.step(32);
+    game.release(KeyCode::Space);
+    game.release(KeyCode::ArrowRight);
+    game.step(1);
+    assert_eq!(game.count::<Bullet>(), 0);
+    assert_eq!(game.player_position(), start);
+
+    game.press(KeyCode::KeyF);
+    game.press(KeyCode::KeyL);
+    game.step(32);
+    assert!(game.count::<Bullet>() > 0);
+    assert!(game.player_position().x > start.x);
+}
+
+#[test]
+fn bindings_detect_conflicts() {
+    let mut bindings = InputBindings::default();
+
+    assert_eq!(
+        bindings.conflict(Action::Fire, KeyCode::KeyW),
+        Some(Action::MoveUp)
+    );
+    // Binding a key to the action that already has it is no conflict
+    assert_eq!(bindings.conflict(Action::MoveUp, KeyCode::KeyW), None);
+
+    bindings.bind(Action::Fire, None, KeyCode::KeyF);
+    assert_eq!(bindings.keys(Action::Fire), [KeyCode::Space, KeyCode::KeyF]);
+    assert_eq!(
+        bindings.conflict(Action::Pause, KeyCode::KeyF),
+        Some(Action::Fire)
+    );
+}
+
+#[test]
+fn gamepad_drives_the_player() {
+    let mut game = TestApp::new();
+    game.world_mut()
+        .resource_mut::<Settings>()
+        .set_gamepad_deadzone(0.2);
+    let gamepad = game.connect_gamepad();
+    let direction = |game: &TestApp| game.world().resource::<PlayerInput>().direction;
+
+    // Inside the deadzone the stick does nothing
+    game.move_stick(gamepad, Vec2::new(0.15, 0.0));
+    game.step(1);
+    assert_eq!(direction(&game), Vec2::ZERO);
+
+    // Past it the stick keeps its analog position, scaled from the deadzone
+    game.move_stick(gamepad, Vec2::new(0.6, 0.0));
+    game.step(1);
+    assert!(
+        (direction(&game).x - 0.5).abs() < 0.001,
+        "{}",
+        direction(&game)
+    );
+
+    // The d-pad moves at full speed, like the keyboard
+    game.move_stick(gamepad, Vec2::ZERO);
+    game.press_button(gamepad, GamepadButton::DPadUp);
+    game.press_button(gamepad, GamepadButton::DPadRight);
+    game.step(1);
+    assert!((direction(&game).length() - 1.0).abs() < 0.001);
+
+    game.press_button(gamepad, GamepadButton::RightTrigger2);
+    game.step(32);
+    assert!(game.count::<Bullet>() > 0);
+
+    game.release_button(gamepad, GamepadButton::RightTrigger2);
+    game.step(1);
+    assert!(!game.world().resource::<PlayerInput>().shoot);
+}
+
+#[test]
+fn gamepad_disconnect_pauses_the_game() {
+    let mut game = TestApp::new();
+    let gamepad = game.connect_gamepad();
+
+    game.disconnect_gamepad(gamepad);
+    game.step(2);
+    assert_eq!(game.game_state(), GameState::Paused);
+
+    // Plugging it back in leaves unpausing to the player
+    game.connect_gamepad();
+    game.step(1);
+    assert_eq!(game.game_state(), GameState::Paused);
+}
@@ -0,0 +1,104 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::{Health, MovementSpeed},
+    enemies::Enemy,
+    game_state::GameState,
+    test_support::TestApp,
+};
+
+/// One small enemy, then two more in a column, straight at the player's guns
+const TWO_WAVES: &str = r#"(
+    stage_clear_time: 1.0,
+    difficulty_step: (speed: 0.1, health: 1.0, fire_rate: 0.0),
+    stages: [
+        (
+            name: "Test",
+            waves: [
+                (delay: 0.0, spawns: [
+                    Group(at: 0.0, enemy: "small", count: 1, x: 0.0, formation: Row(spacing: 0.0)),
+                ]),
+                (delay: 0.5, spawns: [
+                    Group(at: 0.0, enemy: "small", count: 2, x: 0.0, formation: Column(spacing: 60.0)),
+                ]),
+            ],
+        ),
+    ],
+)"#;
+
+fn enemy_positions(game: &mut TestApp) -> Vec<Vec2> {
+    game.world_mut()
+        .query_filtered::<&Transform, With<Enemy>>()
+        .iter(game.world())
+        .map(|transform| transform.translation.truncate())
+        .collect()
+}
+
+#[test]
+fn clearing_every_wave_clears_the_stage() {
+    let mut game = TestApp::with_enemy_spawning();
+    game.load_levels(TWO_WAVES);
+    assert_eq!(game.stage_progress(), (0, 0));
+    assert_eq!(game.enemy_count(), 1);
+
+    game.press(KeyCode::Space);
+    let mut frames = 0;
+    while game.stage_progress() == (0, 0) {
+        game.step(1);
+        frames += 1;
+        assert!(frames < 600, "the first wave should be shot down");
+    }
+    assert_eq!(game.score(), 2);
+
+    // The second wave waits for its delay, then comes in one behind the other
+    game.step(31);
+    assert_eq!(game.enemy_count(), 0);
+    game.step(2);
+    let positions = enemy_positions(&mut game);
+    assert_eq!(positions.len(), 2);
+    assert_eq!(positions[0].x, positions[1].x);
+    assert!((positions[0].y - positions[1].y).abs() > 50.0);
+
+    while game.game_state() == GameState::Playing {
+        game.step(1);
+        frames += 1;
+        assert!(frames < 1200, "the second wave should be shot down");
+    }
+    assert_eq!(game.game_state(), GameState::LevelComplete);
+    assert_eq!(game.stage_progress(), (0, 2));
+    assert_eq!(game.score(), 6);
+
+    // After the break the stages start over, with tougher enemies
+    game.step(66);
+    assert_eq!(game.game_state(), GameState::Playing);
+    assert_eq!(game.stage_progress(), (1, 0));
+    let (speed, health) = game
+        .world_mut()
+        .query_filtered::<(&MovementSpeed, &Health), With<Enemy>>()
+        .single(game.world());
+    assert!((speed.0 - 110.0).abs() < 0.001);
+    assert_eq!(health.0, 4);
+}
+
+#[test]
+fn random_spawner_only_runs_during_its_wave() {
+    let mut game = TestApp::with_enemy_spawning();
+    game.edit_balance(|balance| {
+        balance.enemy_spawn_chance = balance.enemy_spawn_denominator;
+    });
+    game.step(2);
+    game.load_levels(
+        r#"(
+            stage_clear_time: 1.0,
+            difficulty_step: (speed: 0.0, health: 0.0, fire_rate: 0.0),
+            stages: [(name: "Test", waves: [(delay: 0.0, spawns: [Random(at: 0.0, duration: 0.25)])])],
+        )"#,
+    );
+
+    game.step(20);
+    let spawned = game.enemy_count();
+    assert!((15..=17).contains(&spawned), "spawned {spawned}");
+
+    game.step(64);
+    assert_eq!(game.enemy_count(), spawned);
+}
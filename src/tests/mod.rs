@@ -0,0 +1,14 @@
+mod assets;
+mod balance;
+mod bosses;
+mod buffs;
+mod damage;
+mod enemy_movement;
+mod enemy_weapons;
+mod gameplay;
+mod input;
+mod levels;
+mod pointer;
+mod replay;
+mod restart;
+mod settings;
@@ -0,0 +1,49 @@
+use bevy::prelude::*;
+
+use crate::pointer::{joystick_direction, steer_towards, TouchControl};
+
+#[test]
+fn pointer_steering_eases_in_and_respects_bounds() {
+    let area = Rect::new(-100.0, -50.0, 100.0, 50.0);
+
+    // Far away targets are steered to at full speed
+    let direction = steer_towards(Vec2::ZERO, Vec2::new(90.0, 0.0), area);
+    assert_eq!(direction, Vec2::X);
+
+    // Close ones slow the ship down, and it stops on the target
+    let near = steer_towards(Vec2::ZERO, Vec2::new(16.0, 0.0), area);
+    assert!(near.x > 0.0 && near.x < 1.0, "{near}");
+    assert_eq!(steer_towards(Vec2::ONE, Vec2::ONE, area), Vec2::ZERO);
+
+    // A target past the edge stops the ship at the edge instead of pushing
+    // it into the bounds forever
+    let edge = Vec2::new(100.0, 0.0);
+    assert_eq!(steer_towards(edge, Vec2::new(300.0, 0.0), area), Vec2::ZERO);
+}
+
+#[test]
+fn touch_controls_sit_in_the_bottom_corners() {
+    let size = Vec2::new(1280.0, 720.0);
+
+    let joystick = Vec2::new(96.0, 624.0);
+    assert_eq!(
+        TouchControl::at(joystick, size),
+        Some(TouchControl::Joystick)
+    );
+    assert_eq!(
+        TouchControl::at(Vec2::new(1200.0, 640.0), size),
+        Some(TouchControl::FireButton)
+    );
+    assert_eq!(TouchControl::at(size / 2.0, size), None);
+
+    // Dragging up on the joystick steers up, however far the finger goes
+    assert_eq!(joystick_direction(joystick, size), Vec2::ZERO);
+    assert_eq!(
+        joystick_direction(joystick - Vec2::new(0.0, 32.0), size),
+        Vec2::new(0.0, 0.5)
+    );
+    assert_eq!(
+        joystick_direction(joystick - Vec2::new(0.0, 500.0), size),
+        Vec2::Y
+    );
+}
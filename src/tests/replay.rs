@@ -0,0 +1,56 @@
+use bevy::prelude::*;
+
+use crate::{
+    balance::Balance,
+    enemy_defs::EnemyDefs,
+    input::PlayerInput,
+    levels::Levels,
+    replay::{gameplay_data_hash, Checksum, Replay, ReplayHeader},
+    test_support::TestApp,
+};
+
+fn data_hash(game: &TestApp) -> u64 {
+    let world = game.world();
+    gameplay_data_hash(
+        world.resource::<Balance>(),
+        world.resource::<Levels>(),
+        world.resource::<EnemyDefs>(),
+    )
+}
+
+#[test]
+fn replays_read_back_what_was_written() {
+    let idle = PlayerInput::default();
+    let shooting = PlayerInput {
+        direction: Vec2::new(-1.0, 0.5),
+        shoot: true,
+    };
+    let replay = Replay {
+        header: ReplayHeader {
+            game_version: "1.2.3".into(),
+            seed: 42,
+            music_volume: 0.5,
+            effect_volume: 0.25,
+            data_hash: 0x0123_4567_89ab_cdef,
+        },
+        inputs: vec![idle, idle, shooting, idle],
+        checksums: vec![Checksum { tick: 64, hash: 7 }],
+    };
+
+    assert_eq!(Replay::decode(&replay.encode()).unwrap(), replay);
+}
+
+#[test]
+fn the_data_hash_changes_with_the_balance_and_levels() {
+    let mut game = TestApp::new();
+    let loaded = data_hash(&game);
+    assert_eq!(data_hash(&TestApp::new()), loaded);
+
+    game.edit_balance(|balance| balance.player_speed += 1.0);
+    game.step(2);
+    let edited = data_hash(&game);
+    assert_ne!(edited, loaded);
+
+    game.world_mut().resource_mut::<Levels>().stage_clear_time += 1.0;
+    assert_ne!(data_hash(&game), edited);
+}
@@ -0,0 +1,58 @@
+use bevy::prelude::*;
+
+use crate::{
+    game_state::GameState,
+    powerups::PowerupType,
+    test_support::{RunSnapshot, TestApp},
+};
+
+/// Fly around and shoot for a while, snapshotting the run every second
+fn play(game: &mut TestApp) -> Vec<RunSnapshot> {
+    let mut snapshots = vec![game.snapshot()];
+
+    game.press(KeyCode::Space);
+    for (key, frames) in [
+        (KeyCode::ArrowLeft, 100),
+        (KeyCode::ArrowUp, 50),
+        (KeyCode::ArrowRight, 300),
+        (KeyCode::ArrowDown, 150),
+    ] {
+        game.press(key);
+        for _ in 0..frames / 50 {
+            game.step(50);
+            snapshots.push(game.snapshot());
+        }
+        game.release(key);
+    }
+    game.release(KeyCode::Space);
+
+    snapshots
+}
+
+#[test]
+fn restarted_run_matches_a_fresh_one() {
+    let mut fresh = TestApp::with_enemy_spawning();
+    let fresh_run = play(&mut fresh);
+
+    let mut restarted = TestApp::with_enemy_spawning();
+    play(&mut restarted);
+
+    // Leave behind powerups, boosted stats and explosions, then die
+    let position = restarted.player_position();
+    restarted.spawn_powerup(PowerupType::Speed, position);
+    restarted.spawn_powerup(PowerupType::FireRate, Vec2::new(0.0, 300.0));
+    restarted.spawn_enemy("medium", Vec2::new(0.0, 200.0));
+    restarted.step(1);
+    restarted.set_player_health(1);
+    let position = restarted.player_position();
+    restarted.spawn_enemy("large", position);
+    restarted.step(3);
+    assert_eq!(restarted.game_state(), GameState::GameOver);
+
+    restarted.restart();
+    restarted.step(1);
+    let restarted_run = play(&mut restarted);
+
+    assert!(fresh_run.last().unwrap().score > 0);
+    assert_eq!(fresh_run, restarted_run);
+}
@@ -0,0 +1,56 @@
+use bevy::prelude::*;
+
+use crate::{input::Action, pointer::ControlScheme, settings::Settings};
+
+#[test]
+fn settings_survive_a_save_and_load() {
+    let mut settings = Settings::new();
+    settings.set_music_volume(0.2);
+    settings.set_effect_volume(0.9);
+    settings.set_gamepad_deadzone(0.3);
+    settings.control_scheme = ControlScheme::Pointer;
+    settings.bindings.bind(Action::Fire, Some(0), KeyCode::KeyF);
+    settings.seed = Some(7);
+
+    let loaded = Settings::from_ron(&settings.to_ron());
+
+    assert_eq!(loaded.music_volume, 0.2);
+    assert_eq!(loaded.effect_volume, 0.9);
+    assert_eq!(loaded.gamepad_deadzone, 0.3);
+    assert_eq!(loaded.control_scheme, ControlScheme::Pointer);
+    assert_eq!(loaded.bindings, settings.bindings);
+    // Seeds come from the command line, not from the last session
+    assert_eq!(loaded.seed, None);
+}
+
+#[test]
+fn corrupt_settings_fall_back_to_defaults() {
+    let defaults = Settings::new();
+
+    for text in ["", "not ron at all", "(version: 1, music_volume: \"loud\")"] {
+        let loaded = Settings::from_ron(text);
+        assert_eq!(loaded.music_volume, defaults.music_volume, "{text}");
+        assert_eq!(loaded.effect_volume, defaults.effect_volume, "{text}");
+    }
+}
+
+#[test]
+fn settings_from_other_versions_keep_what_they_can() {
+    let defaults = Settings::new();
+
+    // Missing fields get their defaults, unknown ones are ignored and values
+    // are kept in range
+    let loaded = Settings::from_ron("(version: 99, music_volume: 3.0, subtitles: true)");
+
+    assert_eq!(loaded.music_volume, 1.0);
+    assert_eq!(loaded.effect_volume, defaults.effect_volume);
+    assert_eq!(loaded.bindings, defaults.bindings);
+}
+
+#[test]
+fn saved_bindings_gain_new_actions() {
+    let loaded = Settings::from_ron("(version: 2, bindings: {Fire: [KeyF]})");
+
+    assert_eq!(loaded.bindings.keys(Action::Fire), [KeyCode::KeyF]);
+    assert_eq!(loaded.bindings.keys(Action::Pause), [KeyCode::Escape]);
+}